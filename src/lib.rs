@@ -4,9 +4,6 @@
 #[macro_use]
 extern crate serde_derive;
 
-#[macro_use]
-extern crate lazy_static;
-
 extern crate serde;
 
 pub mod poet;
@@ -1,2 +1,282 @@
-#[derive(Serialize, Deserialize, Clone, Debug)]
-pub struct AnnotationSpec {}
+use crate::poet::class_name::StructName;
+use crate::poet::code_block::{Arg, CodeBlock};
+use crate::poet::code_writer::CodeWriter;
+use crate::poet::DEFAULT_INDENT;
+use core::fmt;
+
+///
+/// The element of a declaration an annotation applies to, written before the annotation type as
+/// in `@field:Inject`.
+///
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum UseSiteTarget {
+    FILE,
+    PROPERTY,
+    FIELD,
+    GET,
+    SET,
+    RECEIVER,
+    PARAM,
+    SETPARAM,
+    DELEGATE,
+}
+
+impl UseSiteTarget {
+    pub fn keyword(&self) -> &'static str {
+        match self {
+            UseSiteTarget::FILE => "file",
+            UseSiteTarget::PROPERTY => "property",
+            UseSiteTarget::FIELD => "field",
+            UseSiteTarget::GET => "get",
+            UseSiteTarget::SET => "set",
+            UseSiteTarget::RECEIVER => "receiver",
+            UseSiteTarget::PARAM => "param",
+            UseSiteTarget::SETPARAM => "setparam",
+            UseSiteTarget::DELEGATE => "delegate",
+        }
+    }
+}
+
+/** A generated annotation on a declaration. */
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct AnnotationSpec {
+    pub type_name: StructName,
+    pub members: Vec<CodeBlock>,
+    pub use_site_target: Option<UseSiteTarget>,
+}
+
+impl AnnotationSpec {
+    pub fn new(builder: &AnnotationSpecBuilder) -> Self {
+        AnnotationSpec {
+            type_name: builder.type_name.clone(),
+            members: builder.members.clone(),
+            use_site_target: builder.use_site_target.clone(),
+        }
+    }
+
+    pub fn builder(type_name: StructName) -> AnnotationSpecBuilder {
+        AnnotationSpecBuilder::new(type_name)
+    }
+
+    ///
+    /// Emits this annotation. Members go on one line when `inline` is set; otherwise they are
+    /// written as a wrap group, each on its own line unless they fit within the column limit.
+    ///
+    pub fn emit(&self, writer: &mut CodeWriter, inline: bool) {
+        writer.emit(String::from("@"));
+        if let Some(use_site_target) = &self.use_site_target {
+            writer.emit(format!("{}:", use_site_target.keyword()));
+        }
+        self.emit_body(writer, inline);
+    }
+
+    /// Emits the annotation type and its members, without the leading `@`.
+    fn emit_body(&self, writer: &mut CodeWriter, inline: bool) {
+        writer.emit_code("%T", vec![self.type_name.clone()]);
+        if self.members.is_empty() {
+            return;
+        }
+
+        writer.emit(String::from("("));
+        if inline {
+            for (index, member) in self.members.iter().enumerate() {
+                if index > 0 {
+                    writer.emit(String::from(", "));
+                }
+                writer.emit_block(member);
+            }
+        } else {
+            writer.emit_group(
+                self.members.len(),
+                |writer, index| writer.emit_block(&self.members[index]),
+                |writer| writer.emit(String::from(")")),
+            );
+        }
+        writer.emit(String::from(")"));
+    }
+
+    ///
+    /// Returns this annotation as a value that can be nested in another annotation's members,
+    /// which Kotlin writes without the `@`.
+    ///
+    pub fn to_code_block(&self) -> CodeBlock {
+        let mut format = String::from("%T");
+        let mut args = vec![Arg::from(self.type_name.clone())];
+        if !self.members.is_empty() {
+            let placeholders = vec!["%L"; self.members.len()];
+            format.push_str(&format!("({})", placeholders.join(", ")));
+            args.extend(self.members.iter().cloned().map(Arg::from));
+        }
+        CodeBlock::of(&format, args)
+    }
+}
+
+impl From<AnnotationSpec> for Arg {
+    fn from(annotation: AnnotationSpec) -> Self {
+        Arg::Code(annotation.to_code_block())
+    }
+}
+
+impl fmt::Display for AnnotationSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut out = String::new();
+        let mut writer = CodeWriter::new(&mut out, DEFAULT_INDENT);
+        writer.emit(String::from("@"));
+        if let Some(use_site_target) = &self.use_site_target {
+            writer.emit(format!("{}:", use_site_target.keyword()));
+        }
+        self.emit_body(&mut writer, true);
+        writer.close();
+        write!(f, "{}", out)
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct AnnotationSpecBuilder {
+    pub type_name: StructName,
+    pub members: Vec<CodeBlock>,
+    pub use_site_target: Option<UseSiteTarget>,
+}
+
+impl AnnotationSpecBuilder {
+    pub fn new(type_name: StructName) -> Self {
+        AnnotationSpecBuilder {
+            type_name,
+            members: vec![],
+            use_site_target: None,
+        }
+    }
+
+    ///
+    /// Adds a member such as `name = %S`. Arrays are written with Kotlin's literal syntax, e.g.
+    /// `names = [%S, %S]`, and nested annotations can be passed as `%L` arguments.
+    ///
+    pub fn add_member<T: Into<Arg>>(
+        &mut self,
+        format: &str,
+        args: Vec<T>,
+    ) -> &mut AnnotationSpecBuilder {
+        self.members.push(CodeBlock::of(format, args));
+        self
+    }
+
+    pub fn use_site_target(
+        &mut self,
+        use_site_target: UseSiteTarget,
+    ) -> &mut AnnotationSpecBuilder {
+        self.use_site_target = Some(use_site_target);
+        self
+    }
+
+    pub fn build(&self) -> AnnotationSpec {
+        AnnotationSpec::new(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::poet::annotation_spec::{AnnotationSpec, UseSiteTarget};
+    use crate::poet::class_name::StructName;
+    use crate::poet::code_block::Arg;
+    use crate::poet::code_writer::CodeWriter;
+    use crate::poet::format_options::FormatOptions;
+    use crate::poet::DEFAULT_INDENT;
+
+    #[test]
+    fn members_and_arrays() {
+        let annotation = AnnotationSpec::builder(StructName::new("", "Taco"))
+            .add_member("name = %S", vec!["carnitas"])
+            .add_member("fillings = [%S, %S]", vec!["pork", "salsa"])
+            .build();
+        assert_eq!(
+            "@Taco(name = \"carnitas\", fillings = [\"pork\", \"salsa\"])",
+            format!("{}", annotation)
+        );
+    }
+
+    #[test]
+    fn nested_annotation() {
+        let salsa = AnnotationSpec::builder(StructName::new("", "Salsa"))
+            .add_member("heat = %L", vec!["3"])
+            .build();
+        let taco = AnnotationSpec::builder(StructName::new("", "Taco"))
+            .add_member("%L", vec![Arg::from(salsa)])
+            .build();
+        assert_eq!("@Taco(Salsa(heat = 3))", format!("{}", taco));
+    }
+
+    #[test]
+    fn use_site_target() {
+        let annotation = AnnotationSpec::builder(StructName::new("", "Inject"))
+            .use_site_target(UseSiteTarget::FIELD)
+            .build();
+        assert_eq!("@field:Inject", format!("{}", annotation));
+    }
+
+    #[test]
+    fn long_annotation_wraps_members() {
+        let annotation = AnnotationSpec::builder(StructName::new("", "Taco"))
+            .add_member("description = %S", vec!["a".repeat(60)])
+            .add_member("fillings = [%S, %S]", vec!["pork", "salsa"])
+            .build();
+
+        let mut out = String::new();
+        let mut writer = CodeWriter::new(&mut out, DEFAULT_INDENT);
+        annotation.emit(&mut writer, false);
+        writer.close();
+
+        assert_eq!(
+            format!(
+                "@Taco(\n    description = \"{}\",\n    fillings = [\"pork\", \"salsa\"],\n)",
+                "a".repeat(60)
+            ),
+            out
+        );
+    }
+
+    #[test]
+    fn wrapped_members_without_trailing_comma() {
+        let annotation = AnnotationSpec::builder(StructName::new("", "Taco"))
+            .add_member("description = %S", vec!["b".repeat(8)])
+            .add_member("size = %L", vec!["3"])
+            .build();
+
+        let mut out = String::new();
+        let options = FormatOptions {
+            column_limit: 30,
+            trailing_commas: false,
+            ..FormatOptions::default()
+        };
+        let mut writer = CodeWriter::with_options(&mut out, options);
+        annotation.emit(&mut writer, false);
+        writer.close();
+
+        assert_eq!(
+            format!(
+                "@Taco(\n    description = \"{}\",\n    size = 3\n)",
+                "b".repeat(8)
+            ),
+            out
+        );
+    }
+
+    #[test]
+    fn fit_is_measured_from_current_column() {
+        let annotation = AnnotationSpec::builder(StructName::new("", "Taco"))
+            .add_member("size = %L", vec!["3"])
+            .add_member("heat = %L", vec!["5"])
+            .build();
+
+        let mut out = String::new();
+        let options = FormatOptions {
+            column_limit: 30,
+            ..FormatOptions::default()
+        };
+        let mut writer = CodeWriter::with_options(&mut out, options);
+        writer.emit(String::from("fun eat("));
+        annotation.emit(&mut writer, false);
+        writer.close();
+
+        assert_eq!("fun eat(@Taco(\n    size = 3,\n    heat = 5,\n)", out);
+    }
+}
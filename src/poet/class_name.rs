@@ -1,16 +1,71 @@
-#[derive(Serialize, Deserialize, Clone, Debug)]
+use crate::poet::escape_segments_if_necessary;
+use core::fmt;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct StructName {
     pub package_name: String,
     pub simple_name: String,
 }
 
-impl StructName {}
+impl StructName {
+    pub fn new(package_name: impl Into<String>, simple_name: impl Into<String>) -> Self {
+        StructName {
+            package_name: package_name.into(),
+            simple_name: simple_name.into(),
+        }
+    }
+
+    ///
+    /// Returns a new [StructName] instance for the given fully-qualified name string. This method
+    /// assumes that the input is ASCII and follows typical naming conventions: the simple name
+    /// follows the last dot, everything before it is the package.
+    ///
+    pub fn best_guess(name: &str) -> Self {
+        match name.rfind('.') {
+            Some(index) => StructName::new(&name[..index], &name[index + 1..]),
+            None => StructName::new("", name),
+        }
+    }
+
+    pub fn canonical_name(&self) -> String {
+        let simple_name = escape_segments_if_necessary(&self.simple_name);
+        if self.package_name.is_empty() {
+            return simple_name;
+        }
+
+        format!(
+            "{}.{}",
+            escape_segments_if_necessary(&self.package_name),
+            simple_name
+        )
+    }
+}
+
+impl fmt::Display for StructName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.canonical_name())
+    }
+}
 
 pub struct StructNameBuilder {}
 
 impl StructNameBuilder {
-    pub fn struct_builder(_name: &'static str) {}
-    pub fn object_builder(_name: &'static str) {}
-    pub fn interface_builder(_name: &'static str) {}
-    pub fn enum_builder(_name: &'static str) {}
+    pub fn struct_builder(_name: impl Into<String>) {}
+    pub fn object_builder(_name: impl Into<String>) {}
+    pub fn interface_builder(_name: impl Into<String>) {}
+    pub fn enum_builder(_name: impl Into<String>) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::poet::class_name::StructName;
+
+    #[test]
+    fn best_guess() {
+        let name = StructName::best_guess("com.phodal.Taco");
+        assert_eq!("com.phodal", name.package_name);
+        assert_eq!("Taco", name.simple_name);
+        assert_eq!("com.phodal.Taco", name.canonical_name());
+        assert_eq!("Taco", StructName::best_guess("Taco").canonical_name());
+    }
 }
@@ -1,7 +1,24 @@
+use crate::poet::class_name::StructName;
 use crate::poet::code_writer::CodeWriter;
-use crate::poet::{index_of, DEFAULT_INDENT};
+use crate::poet::member_name::MemberName;
+use crate::poet::type_name::TypeName;
+use crate::poet::{index_of_any, DEFAULT_INDENT};
 use core::fmt;
-use serde::export::Formatter;
+use core::fmt::Formatter;
+
+const SPECIAL_PLACEHOLDERS: [char; 8] = ['%', '⇥', '⇤', '«', '»', '⸨', '⸴', '⸩'];
+
+// Markers around comments. Parsing always splits `⇥` and `⇤` into parts of their own, so no
+// format string can produce these parts.
+pub const LINE_COMMENT_BEGIN: &str = "⇥//";
+pub const LINE_COMMENT_END: &str = "⇤//";
+pub const BLOCK_COMMENT_BEGIN: &str = "⇥/*";
+pub const BLOCK_COMMENT_END: &str = "⇤*/";
+
+// Markers around a wrap group, like the arguments of a call, and between its elements.
+pub const GROUP_BEGIN: &str = "⸨";
+pub const GROUP_SEPARATOR: &str = "⸴";
+pub const GROUP_END: &str = "⸩";
 
 ///
 /// A fragment of a .kt file, potentially containing declarations, statements, and documentation.
@@ -36,11 +53,76 @@ use serde::export::Formatter;
 /// * `«` begins a statement. For multiline statements, every line after the first line is
 ///   double-indented.
 /// * `»` ends a statement.
+/// * `⸨` begins a wrap group, a list of elements like the arguments in `eat(⸨%L⸴%L⸩)`. A group
+///   that doesn't fit on the current line is written with every element on a line of its own.
+/// * `⸴` separates the elements of a wrap group. It is emitted as `, ` when the group fits on one
+///   line. Commas in the elements themselves never split them.
+/// * `⸩` ends a wrap group.
 ///
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
 pub struct CodeBlock {
     pub format_parts: Vec<String>,
-    pub args: Vec<String>,
+    pub args: Vec<Arg>,
+}
+
+///
+/// An argument for a placeholder in a [CodeBlock] format string. Strings convert into any
+/// placeholder that takes text; `%T` additionally accepts a qualified name string, which is
+/// resolved with [StructName::best_guess].
+///
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum Arg {
+    String(String),
+    Type(TypeName),
+    Member(MemberName),
+    Code(CodeBlock),
+}
+
+impl fmt::Display for Arg {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Arg::String(string) => write!(f, "{}", string),
+            Arg::Type(type_name) => write!(f, "{}", type_name),
+            Arg::Member(member_name) => write!(f, "{}", member_name),
+            Arg::Code(code_block) => write!(f, "{}", code_block),
+        }
+    }
+}
+
+impl From<String> for Arg {
+    fn from(string: String) -> Self {
+        Arg::String(string)
+    }
+}
+
+impl From<&str> for Arg {
+    fn from(string: &str) -> Self {
+        Arg::String(String::from(string))
+    }
+}
+
+impl From<TypeName> for Arg {
+    fn from(type_name: TypeName) -> Self {
+        Arg::Type(type_name)
+    }
+}
+
+impl From<StructName> for Arg {
+    fn from(struct_name: StructName) -> Self {
+        Arg::Type(TypeName::Struct(struct_name))
+    }
+}
+
+impl From<MemberName> for Arg {
+    fn from(member_name: MemberName) -> Self {
+        Arg::Member(member_name)
+    }
+}
+
+impl From<CodeBlock> for Arg {
+    fn from(code_block: CodeBlock) -> Self {
+        Arg::Code(code_block)
+    }
 }
 
 impl CodeBlock {
@@ -51,27 +133,44 @@ impl CodeBlock {
         }
     }
 
-    pub fn of(format: &str, args: Vec<String>) -> CodeBlock {
+    pub fn of<T: Into<Arg>>(format: &str, args: Vec<T>) -> CodeBlock {
         let mut builder: CodeBlockBuilder = CodeBlockBuilder::new();
         builder.add(format, args);
         builder.build()
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.format_parts.is_empty()
+    }
+
+    ///
+    /// Returns this block with a trailing newline added if it doesn't already end with one. An
+    /// empty block stays empty.
+    ///
+    pub fn ensure_ends_with_newline(&self) -> CodeBlock {
+        let mut code_block = self.clone();
+        match code_block.format_parts.last() {
+            Some(last) if !last.ends_with('\n') => code_block.format_parts.push(String::from("\n")),
+            _ => {}
+        }
+        code_block
+    }
 }
 
 impl fmt::Display for CodeBlock {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         let mut out = String::new();
         let mut writer = CodeWriter::new(&mut out, DEFAULT_INDENT);
-        writer.emit_block(&self);
+        writer.emit_block(self);
         writer.close();
         write!(f, "{}", out)
     }
 }
 
-#[derive(Serialize, Clone, Debug)]
+#[derive(Serialize, Clone, Debug, Default)]
 pub struct CodeBlockBuilder {
     pub format_parts: Vec<String>,
-    pub args: Vec<String>,
+    pub args: Vec<Arg>,
 }
 
 impl CodeBlockBuilder {
@@ -83,24 +182,31 @@ impl CodeBlockBuilder {
     }
 
     pub fn build(&self) -> CodeBlock {
-        return CodeBlock::new(self);
+        CodeBlock::new(self)
     }
 
-    ///
-    /// @param control_flow the control flow construct and its code, such as "if (foo == 5)".
-    /// Shouldn't contain braces or newline characters.
-    ///
-    pub fn next_control_flow(&mut self, _control_flow: &'static str, _args: String) {}
-
-    pub fn end_control_flow_none(&mut self) {}
+    pub fn is_empty(&self) -> bool {
+        self.format_parts.is_empty()
+    }
 
-    ///
-    /// @param controlFlow the optional control flow construct and its code, such as
-    ///     "while(foo == 20)". Only used for "do/while" control flows.
-    ///
-    pub fn end_control_flow(&mut self, _control_flow: &'static str, _args: String) {}
+    pub fn add_statement<T: Into<Arg>>(
+        &mut self,
+        format: &str,
+        args: Vec<T>,
+    ) -> &mut CodeBlockBuilder {
+        self.format_parts.push(String::from("«"));
+        self.add(format, args);
+        self.format_parts.push(String::from("\n"));
+        self.format_parts.push(String::from("»"));
+        self
+    }
 
-    pub fn add_statement(&mut self, _control_flow: &'static str, _args: String) {}
+    pub fn add_code(&mut self, code_block: &CodeBlock) -> &mut CodeBlockBuilder {
+        self.format_parts
+            .extend(code_block.format_parts.iter().cloned());
+        self.args.extend(code_block.args.iter().cloned());
+        self
+    }
 
     ///
     /// Add code with positional or relative arguments.
@@ -113,36 +219,46 @@ impl CodeBlockBuilder {
     /// Mixing relative and positional arguments in a call to add is invalid and will result in an
     /// error.
     ///
-    pub fn add(&mut self, format: &str, args: Vec<String>) -> &mut CodeBlockBuilder {
+    pub fn add<T: Into<Arg>>(&mut self, format: &str, args: Vec<T>) -> &mut CodeBlockBuilder {
+        let args: Vec<Arg> = args.into_iter().map(|arg| arg.into()).collect();
         let mut has_relative: bool = false;
         let mut has_indexed: bool = false;
-        let mut relative_parameter_count: i32 = 0;
+        let mut relative_parameter_count: usize = 0;
 
         let mut indexed_parameter_count: Vec<i32> = vec![0; args.len()];
 
         let chars: Vec<char> = format.chars().collect();
         let mut p = 0;
-        while p < format.len() {
+        while p < chars.len() {
+            if CodeBlockBuilder::is_no_arg_placeholder(chars[p]) {
+                self.format_parts.push(chars[p].to_string());
+                p += 1;
+                continue;
+            }
+
             if chars[p] != '%' {
-                let mut next_p = index_of(&chars, p + 1);
+                let mut next_p = index_of_any(&chars, &SPECIAL_PLACEHOLDERS, p + 1);
                 if next_p == -1 {
-                    next_p = format.len() as i32;
+                    next_p = chars.len() as i32;
                 }
-                let x: String = format.chars().skip(p).take(next_p as usize - p).collect();
+                let x: String = chars[p..next_p as usize].iter().collect();
                 self.format_parts.push(x);
                 p = next_p as usize;
                 continue;
             }
 
-            p = p + 1; // '%'.
+            p += 1; // '%'.
 
-            let index_start = p.clone();
+            let index_start = p;
             let mut c: char;
             loop {
+                if p >= chars.len() {
+                    panic!("dangling format characters in '{}'", format);
+                }
                 c = chars[p];
-                p = p + 1;
+                p += 1;
 
-                if !('0' <= c && c <= '9') {
+                if !c.is_ascii_digit() {
                     break;
                 }
             }
@@ -157,66 +273,186 @@ impl CodeBlockBuilder {
                 continue;
             }
 
-            let mut index;
+            let index: usize;
             if index_start < index_end {
-                let index_str: String = format
-                    .chars()
-                    .skip(index_start)
-                    .take(index_end - index_start)
-                    .collect();
-                index = index_str.parse::<i32>().unwrap() - 1;
+                let index_str: String = chars[index_start..index_end].iter().collect();
+                let position = index_str.parse::<usize>().unwrap();
+                if position == 0 {
+                    panic!(
+                        "index 0 for '{}' not in range (received {} arguments)",
+                        format,
+                        args.len()
+                    );
+                }
+                index = position - 1;
 
                 has_indexed = true;
-                if args.len() != 0 {
-                    let modulo = index as usize % args.len(); // modulo is needed, checked below anyway
-                    indexed_parameter_count[modulo] = indexed_parameter_count[modulo] + 1;
+                if !args.is_empty() {
+                    let modulo = index % args.len(); // modulo is needed, checked below anyway
+                    indexed_parameter_count[modulo] += 1;
                 }
             } else {
                 index = relative_parameter_count;
                 has_relative = true;
-                relative_parameter_count = relative_parameter_count + 1;
+                relative_parameter_count += 1;
+            }
+
+            if index >= args.len() {
+                panic!(
+                    "index {} for '{}' not in range (received {} arguments)",
+                    index + 1,
+                    format,
+                    args.len()
+                );
             }
 
-            self.add_argument(format, c, args[index as usize].clone());
+            if has_indexed && has_relative {
+                panic!("cannot mix indexed and positional parameters");
+            }
+
+            self.add_argument(format, c, args[index].clone());
             let merge_char = CodeBlockBuilder::merge_str_c("%", c);
             self.format_parts.push(merge_char);
         }
 
-        // todo: add unused check
-        // if has_indexed {
-        //     let unused = vec![];
-        // }
+        if has_relative && relative_parameter_count < args.len() {
+            panic!(
+                "unused arguments: expected {}, received {}",
+                relative_parameter_count,
+                args.len()
+            );
+        }
+
+        if has_indexed {
+            let unused: Vec<String> = indexed_parameter_count
+                .iter()
+                .enumerate()
+                .filter(|(_, count)| **count == 0)
+                .map(|(index, _)| format!("%{}", index + 1))
+                .collect();
+            if !unused.is_empty() {
+                let s = if unused.len() == 1 { "" } else { "s" };
+                panic!("unused argument{}: {}", s, unused.join(", "));
+            }
+        }
 
         self
     }
 
-    fn is_multi_char_no_arg_placeholder(c: char) -> bool {
-        return c == '%';
+    fn is_no_arg_placeholder(c: char) -> bool {
+        c == '⇥' || c == '⇤' || c == '«' || c == '»' || c == '⸨' || c == '⸴' || c == '⸩'
     }
 
     fn merge_str_c(s: &str, c: char) -> String {
         let mut string = String::from(s);
-        string.push_str(&*c.to_string());
+        string.push(c);
         string
     }
 
-    pub fn add_argument(&mut self, format: &str, c: char, arg: String) {
+    pub fn add_argument(&mut self, format: &str, c: char, arg: Arg) {
         match c {
+            'N' => {
+                self.args.push(self.arg_to_name(arg));
+            }
             'L' => {
                 self.args.push(self.arg_to_literal(arg));
             }
             'S' => {
-                self.args.push(self.arg_to_literal(arg));
+                self.args.push(self.arg_to_string(arg));
             }
             'T' => {
-                self.args.push(self.arg_to_literal(arg));
+                self.args.push(self.arg_to_type(arg));
             }
-            _ => println!("invalid format string: {:?}", format),
+            'M' => {
+                self.args.push(self.arg_to_member(arg));
+            }
+            _ => panic!("invalid format string: '{}'", format),
+        }
+    }
+
+    pub fn arg_to_name(&self, arg: Arg) -> Arg {
+        match arg {
+            Arg::String(_) => arg,
+            _ => panic!("expected name but was {}", arg),
+        }
+    }
+
+    pub fn arg_to_literal(&self, arg: Arg) -> Arg {
+        arg
+    }
+
+    pub fn arg_to_string(&self, arg: Arg) -> Arg {
+        match arg {
+            Arg::String(_) => arg,
+            _ => panic!("expected string but was {}", arg),
+        }
+    }
+
+    pub fn arg_to_type(&self, arg: Arg) -> Arg {
+        match arg {
+            Arg::String(name) => Arg::Type(TypeName::best_guess(&name)),
+            Arg::Type(_) => arg,
+            _ => panic!("expected type but was {}", arg),
+        }
+    }
+
+    pub fn arg_to_member(&self, arg: Arg) -> Arg {
+        match arg {
+            Arg::Member(_) => arg,
+            _ => panic!("expected member but was {}", arg),
         }
     }
 
-    pub fn arg_to_literal(&self, arg: String) -> String {
-        return arg;
+    ///
+    /// Adds a `// ` comment on lines of its own. Long comments wrap, and every continuation line
+    /// starts with `// ` too.
+    ///
+    pub fn add_comment<T: Into<Arg>>(
+        &mut self,
+        format: &str,
+        args: Vec<T>,
+    ) -> &mut CodeBlockBuilder {
+        self.add_delimited(LINE_COMMENT_BEGIN, format, args, LINE_COMMENT_END)
+    }
+
+    ///
+    /// Adds a `/* ... */` comment with each line, including continuation lines, starting with ` * `.
+    ///
+    pub fn add_block_comment<T: Into<Arg>>(
+        &mut self,
+        format: &str,
+        args: Vec<T>,
+    ) -> &mut CodeBlockBuilder {
+        self.add_delimited(BLOCK_COMMENT_BEGIN, format, args, BLOCK_COMMENT_END)
+    }
+
+    fn add_delimited<T: Into<Arg>>(
+        &mut self,
+        begin: &str,
+        format: &str,
+        args: Vec<T>,
+        end: &str,
+    ) -> &mut CodeBlockBuilder {
+        self.ensure_new_line();
+        self.format_parts.push(String::from(begin));
+        self.add(format, args);
+        self.ensure_new_line();
+        self.format_parts.push(String::from(end));
+        self
+    }
+
+    /// Ends the current line unless the last emitted text already did so.
+    fn ensure_new_line(&mut self) {
+        let last_text = self
+            .format_parts
+            .iter()
+            .rev()
+            .find(|part| !part.starts_with(&['⇥', '⇤', '«', '»', '⸨', '⸴', '⸩'][..]));
+        if let Some(last_text) = last_text {
+            if !last_text.ends_with('\n') {
+                self.format_parts.push(String::from("\n"));
+            }
+        }
     }
 
     pub fn unindent(&mut self) -> &mut CodeBlockBuilder {
@@ -224,7 +460,7 @@ impl CodeBlockBuilder {
         self
     }
 
-    pub fn indent<'a>(&mut self) -> &mut CodeBlockBuilder {
+    pub fn indent(&mut self) -> &mut CodeBlockBuilder {
         self.format_parts.push(String::from("⇥"));
         self
     }
@@ -233,6 +469,7 @@ impl CodeBlockBuilder {
 #[cfg(test)]
 mod tests {
     use crate::poet::code_block::{CodeBlock, CodeBlockBuilder};
+    use crate::poet::method_spec::MethodSpec;
 
     #[test]
     fn of() {
@@ -288,4 +525,117 @@ mod tests {
     //     let result = format!("{} || {} || {}", blocks[0], blocks[1], blocks[2]);
     //     assert_eq!("\"hello\" || world.World || need tacos", result);
     // }
+
+    #[test]
+    fn group_that_fits_stays_on_one_line() {
+        let block = CodeBlock::of("eat(⸨%L⸴%L⸩)", vec!["taco", "burrito"]);
+        assert_eq!("eat(taco, burrito)", block.to_string());
+    }
+
+    #[test]
+    fn group_that_doesnt_fit_wraps_every_element() {
+        let tacos = ["taco"; 20].join("_");
+        let block = CodeBlockBuilder::new()
+            .add_statement("eat(⸨%L⸴%L⸴%S⸩)", vec![tacos.as_str(), "1", "burrito"])
+            .build();
+        assert_eq!(
+            format!("eat(\n    {},\n    1,\n    \"burrito\",\n)\n", tacos),
+            block.to_string()
+        );
+    }
+
+    #[test]
+    fn group_wraps_when_the_rest_of_the_line_doesnt_fit() {
+        let tacos = ["taco"; 8].join("_");
+        let burritos = ["burrito"; 5].join("_");
+        let block = CodeBlockBuilder::new()
+            .add_statement(
+                "eat(⸨%L⸴%L⸩).with_extra_salsa_please()",
+                vec![tacos.as_str(), burritos.as_str()],
+            )
+            .build();
+        assert_eq!(
+            format!(
+                "eat(\n    {},\n    {},\n).with_extra_salsa_please()\n",
+                tacos, burritos
+            ),
+            block.to_string()
+        );
+    }
+
+    #[test]
+    fn nested_group_wraps_independently() {
+        let tacos = ["taco"; 20].join("_");
+        let block = CodeBlock::of("eat(⸨%L⸴serve(⸨%L⸴%L⸩)⸩)", vec![tacos.as_str(), "a", "b"]);
+        assert_eq!(
+            format!("eat(\n    {},\n    serve(a, b),\n)", tacos),
+            block.to_string()
+        );
+    }
+
+    #[test]
+    fn group_elements_keep_their_own_commas() {
+        let tacos = ["taco"; 20].join("_");
+        let block = CodeBlock::of(
+            "eat(⸨%L⸴pair(a, b)⸴%S⸩)",
+            vec![tacos.as_str(), "salsa, verde"],
+        );
+        assert_eq!(
+            format!(
+                "eat(\n    {},\n    pair(a, b),\n    \"salsa, verde\",\n)",
+                tacos
+            ),
+            block.to_string()
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "group separator ⸴ is outside of a wrap group")]
+    fn separator_outside_group() {
+        CodeBlock::of("eat(%L⸴%L)", vec!["taco", "burrito"]).to_string();
+    }
+
+    #[test]
+    #[should_panic(expected = "group begin ⸨ has no matching group end ⸩")]
+    fn unclosed_group() {
+        CodeBlock::of("eat(⸨%L)", vec!["taco"]).to_string();
+    }
+
+    #[test]
+    fn line_comment() {
+        let block = CodeBlockBuilder::new()
+            .add("val a = 1", Vec::<String>::new())
+            .add_comment("%L o'clock", vec!["taco"])
+            .add_statement("val b = %L", vec!["2"])
+            .build();
+        assert_eq!("val a = 1\n// taco o'clock\nval b = 2\n", block.to_string());
+    }
+
+    #[test]
+    fn long_line_comment_wraps_with_prefix() {
+        let words = ["taco"; 30].join(" ");
+        let method = MethodSpec::builder("lunch")
+            .add_comment(&words, Vec::<String>::new())
+            .build();
+        assert_eq!(
+            format!(
+                "fun lunch() {{\n    // {}\n    // {}\n}}\n",
+                ["taco"; 18].join(" "),
+                ["taco"; 12].join(" ")
+            ),
+            method.to_string()
+        );
+    }
+
+    #[test]
+    fn block_comment() {
+        let block = CodeBlockBuilder::new()
+            .add_block_comment("first line\n\nsecond */ line", Vec::<String>::new())
+            .add_statement("val a = %L", vec!["1"])
+            .build();
+        assert_eq!(
+            "/*\n * first line\n *\n * second *&#47; line\n */\nval a = 1\n",
+            block.to_string()
+        );
+    }
 }
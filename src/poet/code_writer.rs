@@ -1,12 +1,22 @@
+use crate::poet::annotation_spec::AnnotationSpec;
 use crate::poet::class_name::StructName;
-use crate::poet::code_block::CodeBlock;
+use crate::poet::code_block::{
+    Arg, CodeBlock, BLOCK_COMMENT_BEGIN, BLOCK_COMMENT_END, GROUP_BEGIN, GROUP_END,
+    GROUP_SEPARATOR, LINE_COMMENT_BEGIN, LINE_COMMENT_END,
+};
+use crate::poet::format_options::{BraceStyle, FormatOptions};
 use crate::poet::import::Import;
 use crate::poet::line_wrapper::LineWrapper;
 use crate::poet::member_name::MemberName;
-use crate::poet::string_literal_with_quotes;
-use std::collections::HashMap;
+use crate::poet::modifier::Modifier;
+use crate::poet::{
+    display_width, escape_if_necessary, escape_segments_if_necessary, string_literal_with_quotes,
+};
+use std::collections::BTreeMap;
+use std::fmt;
 
-pub const NO_PACKAGE: &'static str = "";
+pub const NO_PACKAGE: &str = "";
+const KDOC_PREFIX: &str = " * ";
 ///
 /// Converts a [FileSpec] to a string suitable to both human- and kotlinc-consumption. This honors
 /// imports, indentation, and deferred variable names.
@@ -14,46 +24,68 @@ pub const NO_PACKAGE: &'static str = "";
 #[derive(Serialize, Debug)]
 pub struct CodeWriter<'a> {
     pub out: Box<LineWrapper<'a>>,
-    pub indent: &'static str,
     pub package_name: String,
-    pub imports: HashMap<String, Import>,
-    pub import_types: HashMap<String, StructName>,
-    pub import_members: HashMap<String, MemberName>,
-    pub column_limit: i32,
+    pub imports: BTreeMap<String, Import>,
+    pub import_types: BTreeMap<String, StructName>,
+    pub import_members: BTreeMap<String, MemberName>,
+    pub importable_types: BTreeMap<String, Vec<StructName>>,
+    pub importable_members: BTreeMap<String, Vec<MemberName>>,
+    pub options: FormatOptions,
     pub indent_level: i32,
     pub statement_line: i32,
+    /// Written at the start of every line while emitting a comment, like ` * ` or `// `.
+    comment_prefix: &'static str,
     trailing_newline: bool,
 }
 
 impl<'a> CodeWriter<'a> {
-    pub fn new(out: &'a mut String, ident: &'static str) -> Self {
-        let line_wrapper = LineWrapper::new(out, String::from(ident), 100);
+    pub fn new(out: &'a mut dyn fmt::Write, indent: impl Into<String>) -> Self {
+        let options = FormatOptions {
+            indent: indent.into(),
+            ..FormatOptions::default()
+        };
+        CodeWriter::with_options(out, options)
+    }
+
+    pub fn with_options(out: &'a mut dyn fmt::Write, options: FormatOptions) -> Self {
+        let mut line_wrapper = LineWrapper::new(out, options.indent.clone(), options.column_limit);
+        line_wrapper.unsafe_line_starts = options.unsafe_line_starts.clone();
         let wrapper = Box::new(line_wrapper);
         CodeWriter {
             out: wrapper,
-            indent: ident,
             package_name: String::from(NO_PACKAGE),
             imports: Default::default(),
             import_types: Default::default(),
             import_members: Default::default(),
-            column_limit: 100,
+            importable_types: Default::default(),
+            importable_members: Default::default(),
+            options,
             indent_level: 0,
             statement_line: -1,
+            comment_prefix: "",
             trailing_newline: false,
         }
     }
 
-    pub fn emit_code(&mut self, format: &str, args: Vec<String>) {
-        CodeBlock::of(format, args);
+    pub fn emit_code<T: Into<Arg>>(&mut self, format: &str, args: Vec<T>) {
+        self.emit_block(&CodeBlock::of(format, args));
     }
 
     pub fn indent(&mut self, levels: i32) {
-        self.indent_level = self.indent_level + 1;
+        self.indent_level += levels;
+    }
+
+    pub fn unindent(&mut self, levels: i32) {
+        if self.indent_level - levels < 0 {
+            panic!("cannot unindent {} from {}", levels, self.indent_level);
+        }
+        self.indent_level -= levels;
     }
 
     pub fn emit(&mut self, s: String) {
         let mut first = true;
-        for line in s.split("\n") {
+        let mut lines = s.split('\n').peekable();
+        while let Some(line) = lines.next() {
             if !first {
                 self.out.new_line();
                 self.trailing_newline = true;
@@ -62,61 +94,441 @@ impl<'a> CodeWriter<'a> {
                     if self.statement_line == 0 {
                         self.indent(2);
                     }
-                    self.statement_line = self.statement_line + 1
+                    self.statement_line += 1
                 }
             }
 
             first = false;
 
-            if line.len() == 0 {
+            if line.is_empty() {
+                // Blank lines inside a comment still carry the comment gutter, like ` *`.
+                if !self.comment_prefix.is_empty()
+                    && self.trailing_newline
+                    && lines.peek().is_some()
+                {
+                    self.emit_indentation();
+                    let gutter = self.comment_prefix.trim_end();
+                    self.out.append_non_wrapping(String::from(gutter));
+                }
                 continue;
             }
 
             if self.trailing_newline {
-                // emitIndentation
+                self.emit_indentation();
+                self.out
+                    .append_non_wrapping(String::from(self.comment_prefix));
             }
 
+            // Doc text can't close the doc comment it is written in.
+            let line = if self.comment_prefix == KDOC_PREFIX {
+                line.replace("*/", "*&#47;")
+            } else {
+                String::from(line)
+            };
             self.out.append(
-                String::from(line),
-                Some(self.indent_level + 2),
-                Some(String::from("")),
+                line,
+                // Wrapped code is double-indented; wrapped comments stay aligned with their gutter.
+                Some(if self.comment_prefix.is_empty() {
+                    self.indent_level + 2
+                } else {
+                    self.indent_level
+                }),
+                Some(String::from(self.comment_prefix)),
             );
             self.trailing_newline = false;
         }
     }
 
+    fn emit_indentation(&mut self) {
+        for _ in 0..self.indent_level {
+            self.out.append_non_wrapping(self.options.indent.clone());
+        }
+    }
+
     pub fn emit_block(&mut self, code_block: &CodeBlock) {
         self._emit_code(code_block);
     }
 
     fn _emit_code(&mut self, code_block: &CodeBlock) {
         let mut a: usize = 0;
-        for part in code_block.format_parts.iter() {
-            match &*part.clone() {
-                "%L" => {
-                    self.emit_literal(code_block.args[a].clone());
-                    a = a + 1;
+        let mut p: usize = 0;
+        while p < code_block.format_parts.len() {
+            let part = &code_block.format_parts[p];
+            p += 1;
+            match part.as_str() {
+                "%N" => {
+                    match &code_block.args[a] {
+                        Arg::String(name) => self.emit(escape_if_necessary(name)),
+                        arg => self.emit_literal(arg),
+                    }
+                    a += 1;
+                }
+                "%L" | "%T" | "%M" => {
+                    self.emit_literal(&code_block.args[a]);
+                    a += 1;
                 }
                 "%S" => {
-                    let string = code_block.args[a].clone();
-                    let literal = string_literal_with_quotes(&*string, self.indent);
+                    let string = code_block.args[a].to_string();
+                    let literal = string_literal_with_quotes(&string, &self.options.indent);
                     self.emit(literal);
+                    a += 1;
                 }
-                "%T" => {
-                    self.emit_literal(code_block.args[a].clone());
-                    a = a + 1;
+                "%%" => {
+                    self.emit(String::from("%"));
                 }
+                "⇥" => {
+                    self.indent(1);
+                }
+                "⇤" => {
+                    self.unindent(1);
+                }
+                "«" => {
+                    if self.statement_line != -1 {
+                        panic!("statement enter » followed by statement enter «");
+                    }
+                    self.statement_line = 0;
+                }
+                "»" => {
+                    if self.statement_line == -1 {
+                        panic!("statement exit » has no matching statement enter «");
+                    }
+                    if self.statement_line > 0 {
+                        self.unindent(2); // End a multi-line statement. Decrease the indentation level.
+                    }
+                    self.statement_line = -1;
+                }
+                LINE_COMMENT_BEGIN => {
+                    self.trailing_newline = true;
+                    self.comment_prefix = "// ";
+                }
+                BLOCK_COMMENT_BEGIN => {
+                    self.trailing_newline = true;
+                    self.emit(String::from("/*\n"));
+                    self.comment_prefix = KDOC_PREFIX;
+                }
+                LINE_COMMENT_END => {
+                    self.comment_prefix = "";
+                }
+                BLOCK_COMMENT_END => {
+                    self.comment_prefix = "";
+                    self.emit(String::from(" */\n"));
+                }
+                GROUP_BEGIN => {
+                    let (elements, parts_used, args_used) =
+                        split_group(&code_block.format_parts[p..], &code_block.args[a..]);
+                    let rest_of_line = rest_of_line(
+                        &code_block.format_parts[p + parts_used..],
+                        &code_block.args[a + args_used..],
+                    );
+                    self.emit_group(
+                        elements.len(),
+                        |writer, index| writer.emit_block(&elements[index]),
+                        |writer| writer.emit_block(&rest_of_line),
+                    );
+                    p += parts_used;
+                    a += args_used;
+                }
+                GROUP_SEPARATOR => panic!("group separator ⸴ is outside of a wrap group"),
+                GROUP_END => panic!("group end ⸩ has no matching group begin ⸨"),
                 _ => {
-                    // Handle deferred type.
-                    // println!("Handle deferred type");
                     self.emit(part.clone());
                 }
             }
         }
     }
 
-    pub fn emit_literal(&mut self, arg: String) {
-        self.emit(arg);
+    ///
+    /// Emits a comma-separated list of `len` elements, each written by `emit_element`. The list
+    /// goes on the current line if all of it, together with the rest of its line, fits there.
+    /// Otherwise every element goes on a line of its own, one level deeper, and the closing
+    /// delimiter that follows starts a new line.
+    ///
+    /// `rest_of_line` writes what follows the list, like the closing `)` and a return type. It is
+    /// only measured, never emitted: the caller emits that code itself after the group.
+    ///
+    pub fn emit_group<F, R>(&mut self, len: usize, mut emit_element: F, rest_of_line: R)
+    where
+        F: FnMut(&mut CodeWriter, usize),
+        R: FnOnce(&mut CodeWriter),
+    {
+        let mut emit_inline = |writer: &mut CodeWriter| {
+            for index in 0..len {
+                if index > 0 {
+                    writer.emit(String::from(", "));
+                }
+                emit_element(writer, index);
+            }
+        };
+
+        let group = self.render_scratch(&mut emit_inline);
+        let fits = !group.contains('\n') && {
+            let line = self.render_scratch(|writer| {
+                emit_inline(writer);
+                rest_of_line(writer);
+            });
+            let first_line = line.split('\n').next().unwrap_or_default();
+            self.out.current_column() + display_width(first_line)
+                <= self.options.column_limit as usize
+        };
+
+        if fits {
+            emit_inline(self);
+            return;
+        }
+
+        // Statement continuation indentation doesn't apply inside the group; it has its own.
+        let statement_line = self.statement_line;
+        self.statement_line = -1;
+        self.indent(1);
+        self.emit(String::from("\n"));
+        for index in 0..len {
+            emit_element(self, index);
+            if index + 1 < len || self.options.trailing_commas {
+                self.emit(String::from(","));
+            }
+            self.emit(String::from("\n"));
+        }
+        self.unindent(1);
+        self.statement_line = statement_line;
+    }
+
+    ///
+    /// Returns the code written by `emit` to a scratch writer that sees the same imports and
+    /// statement as this one. The scratch writer never wraps, so every line break in the result
+    /// was written explicitly.
+    ///
+    fn render_scratch<F>(&self, emit: F) -> String
+    where
+        F: FnOnce(&mut CodeWriter),
+    {
+        let mut out = String::new();
+        let options = FormatOptions {
+            column_limit: i32::MAX,
+            ..self.options.clone()
+        };
+        let mut writer = CodeWriter::with_options(&mut out, options);
+        writer.package_name = self.package_name.clone();
+        writer.imports = self.imports.clone();
+        writer.import_types = self.import_types.clone();
+        writer.import_members = self.import_members.clone();
+        writer.indent_level = self.indent_level;
+        writer.statement_line = self.statement_line;
+        emit(&mut writer);
+        writer.close();
+        out
+    }
+
+    ///
+    /// Emits `kdoc` as a `/** ... */` doc comment. Nothing is emitted for an empty block.
+    ///
+    pub fn emit_kdoc(&mut self, kdoc: &CodeBlock) {
+        if kdoc.is_empty() {
+            return;
+        }
+
+        self.emit(String::from("/**\n"));
+        self.comment_prefix = KDOC_PREFIX;
+        self.emit_block(kdoc);
+        self.comment_prefix = "";
+        if !self.trailing_newline {
+            self.emit(String::from("\n"));
+        }
+        self.emit(String::from(" */\n"));
+    }
+
+    ///
+    /// Emits `comment` as `// ` line comments, ending on a new line.
+    ///
+    pub fn emit_comment(&mut self, comment: &CodeBlock) {
+        self.trailing_newline = true; // Force the '//' prefix for the comment.
+        self.comment_prefix = "// ";
+        self.emit_block(comment);
+        self.comment_prefix = "";
+        if !self.trailing_newline {
+            self.emit(String::from("\n"));
+        }
+    }
+
+    pub fn emit_literal(&mut self, arg: &Arg) {
+        match arg {
+            Arg::String(string) => self.emit(string.clone()),
+            Arg::Type(type_name) => type_name.emit(self),
+            Arg::Member(member_name) => member_name.emit(self),
+            Arg::Code(code_block) => self.emit_block(code_block),
+        }
+    }
+
+    ///
+    /// Opens a body, such as a function's, with a brace placed according to the brace style.
+    ///
+    pub fn emit_open_brace(&mut self) {
+        match self.options.brace_style {
+            BraceStyle::SameLine => self.emit(String::from(" {\n")),
+            BraceStyle::NextLine => self.emit(String::from("\n{\n")),
+        }
+    }
+
+    /// Separates two members of a file or type body with blank lines.
+    pub fn emit_member_separator(&mut self) {
+        for _ in 0..self.options.blank_lines_between_members {
+            self.emit(String::from("\n"));
+        }
+    }
+
+    ///
+    /// Emits `annotations` separated by spaces when `inline` is set, or one per line otherwise.
+    ///
+    pub fn emit_annotations(&mut self, annotations: &[AnnotationSpec], inline: bool) {
+        for annotation in annotations {
+            annotation.emit(self, inline);
+            self.emit(String::from(if inline { " " } else { "\n" }));
+        }
+    }
+
+    ///
+    /// Emits `modifiers` in their canonical order, each followed by a space. Duplicates are
+    /// written once.
+    ///
+    pub fn emit_modifiers(&mut self, modifiers: &[Modifier]) {
+        let mut modifiers = modifiers.to_vec();
+        modifiers.sort();
+        modifiers.dedup();
+        for modifier in modifiers {
+            self.emit(modifier.value().keyword);
+            self.emit(String::from(" "));
+        }
+    }
+
+    ///
+    /// Returns the best name to identify `struct_name` with in the current context. This uses the
+    /// available imports and the current scope to find the shortest name available. It does not
+    /// honor names visible due to inheritance.
+    ///
+    pub fn lookup_name(&mut self, struct_name: &StructName) -> String {
+        let simple_name = escape_segments_if_necessary(&struct_name.simple_name);
+        if struct_name.package_name.is_empty() {
+            return simple_name;
+        }
+
+        // Mark the type as importable for a future pass.
+        let candidates = self
+            .importable_types
+            .entry(struct_name.simple_name.clone())
+            .or_default();
+        if !candidates.contains(struct_name) {
+            candidates.push(struct_name.clone());
+        }
+
+        match self.import_types.get(&struct_name.simple_name) {
+            Some(imported) if imported == struct_name => simple_name,
+            Some(_) => struct_name.canonical_name(),
+            None => {
+                if struct_name.package_name == self.package_name {
+                    return simple_name;
+                }
+                struct_name.canonical_name()
+            }
+        }
+    }
+
+    ///
+    /// Returns the best name to reference `member_name` with. Top-level members and extensions are
+    /// imported when possible; extensions can't be called through a qualified name, so they rely
+    /// on that import, under an alias if their simple name is taken.
+    ///
+    pub fn lookup_member_name(&mut self, member_name: &MemberName) -> String {
+        let simple_name = &member_name.simple_name;
+        let escaped_name = escape_if_necessary(simple_name);
+        let canonical_name = member_name.canonical_name();
+        if let Some((alias, _)) = self
+            .import_members
+            .iter()
+            .find(|(alias, imported)| *alias != simple_name && **imported == *member_name)
+        {
+            return escape_if_necessary(alias);
+        }
+        if let Some(imported) = self.import_members.get(simple_name) {
+            if imported.canonical_name() == canonical_name {
+                return escaped_name;
+            }
+            if let Some(enclosing) = &member_name.enclosing_class_name {
+                return format!("{}.{}", self.lookup_name(enclosing), escaped_name);
+            }
+        }
+
+        // If the member is in the same package, we're done.
+        if member_name.package_name == self.package_name
+            && member_name.enclosing_class_name.is_none()
+        {
+            return escaped_name;
+        }
+
+        // Mark the member as importable for a future pass.
+        let candidates = self
+            .importable_members
+            .entry(simple_name.clone())
+            .or_default();
+        // Operators like `>` and `<` share one `compareTo` import.
+        if !candidates
+            .iter()
+            .any(|c| c.canonical_name() == canonical_name)
+        {
+            candidates.push(member_name.clone());
+        }
+
+        canonical_name
+    }
+
+    ///
+    /// Returns the members that should have been imported for this code, keyed by the name they
+    /// are imported as. That is their simple name, unless it collides with another referenced
+    /// member or type: colliding extensions are then imported under an alias like `bar2`, and
+    /// other colliding members are left fully-qualified.
+    ///
+    pub fn suggested_member_imports(&self) -> BTreeMap<String, MemberName> {
+        let mut result = BTreeMap::new();
+        let mut clashing = vec![];
+        for (simple_name, candidates) in self.importable_members.iter() {
+            if candidates.len() == 1 && !self.importable_types.contains_key(simple_name) {
+                result.insert(simple_name.clone(), candidates[0].clone());
+            } else {
+                clashing.extend(candidates.iter().filter(|c| c.is_extension));
+            }
+        }
+
+        for member_name in clashing {
+            if member_name.operator.is_some() {
+                panic!(
+                    "operator extension {} clashes with another import named {}; operators can't be imported under an alias",
+                    member_name.canonical_name(),
+                    member_name.simple_name
+                );
+            }
+            let alias = (2..)
+                .map(|n| format!("{}{}", member_name.simple_name, n))
+                .find(|alias| {
+                    !result.contains_key(alias)
+                        && !self.importable_members.contains_key(alias)
+                        && !self.importable_types.contains_key(alias)
+                })
+                .unwrap();
+            result.insert(alias, member_name.clone());
+        }
+        result
+    }
+
+    ///
+    /// Returns the types that should have been imported for this code. Types whose simple name
+    /// collides with another referenced type are left fully-qualified.
+    ///
+    pub fn suggested_imports(&self) -> BTreeMap<String, StructName> {
+        let mut result = BTreeMap::new();
+        for (simple_name, candidates) in self.importable_types.iter() {
+            if candidates.len() == 1 {
+                result.insert(simple_name.clone(), candidates[0].clone());
+            }
+        }
+        result
     }
 
     pub fn push_package(&mut self, package_name: &str) {
@@ -126,6 +538,83 @@ impl<'a> CodeWriter<'a> {
     pub fn close(&mut self) {
         self.out.close();
     }
+
+    /// Returns the first error the output sink returned, if any.
+    pub fn result(&self) -> fmt::Result {
+        self.out.result()
+    }
+}
+
+///
+/// Splits the parts of a wrap group, which start right after its `⸨`, into one code block per
+/// element at each `⸴` of the group itself. Returns the elements and how many parts, including the
+/// closing `⸩`, and arguments the group took up.
+///
+fn split_group(parts: &[String], args: &[Arg]) -> (Vec<CodeBlock>, usize, usize) {
+    let mut elements: Vec<CodeBlock> = vec![];
+    let mut element = CodeBlock::default();
+    let mut depth = 0;
+    let mut a: usize = 0;
+    let mut p: usize = 0;
+    loop {
+        if p == parts.len() {
+            panic!("group begin ⸨ has no matching group end ⸩");
+        }
+        let part = &parts[p];
+        p += 1;
+        match part.as_str() {
+            GROUP_BEGIN => depth += 1,
+            GROUP_END if depth == 0 => break,
+            GROUP_END => depth -= 1,
+            GROUP_SEPARATOR if depth == 0 => {
+                elements.push(element);
+                element = CodeBlock::default();
+                continue;
+            }
+            "%%" => {}
+            _ if part.starts_with('%') => {
+                element.args.push(args[a].clone());
+                a += 1;
+            }
+            _ => {}
+        }
+        element.format_parts.push(part.clone());
+    }
+    if !element.is_empty() {
+        elements.push(element);
+    }
+    (elements, p, a)
+}
+
+///
+/// Returns the code of `parts` up to its first line break outside of a wrap group, to measure what
+/// follows a group on its line.
+///
+fn rest_of_line(parts: &[String], args: &[Arg]) -> CodeBlock {
+    let mut rest = CodeBlock::default();
+    let mut depth = 0;
+    let mut a: usize = 0;
+    for part in parts {
+        match part.as_str() {
+            GROUP_BEGIN => depth += 1,
+            GROUP_END => depth -= 1,
+            // Indentation only applies to later lines.
+            "⇥" | "⇤" => continue,
+            "%%" => {}
+            _ if part.starts_with('%') => {
+                rest.args.push(args[a].clone());
+                a += 1;
+            }
+            _ if depth == 0 && part.contains('\n') => {
+                let line = part.split('\n').next().unwrap_or_default();
+                rest.format_parts.push(String::from(line));
+                break;
+            }
+            _ => {}
+        }
+        rest.format_parts.push(part.clone());
+    }
+    rest
 }
 
 #[cfg(test)]
@@ -1,6 +1,24 @@
+use crate::poet::annotation_spec::{AnnotationSpec, UseSiteTarget};
+use crate::poet::code_block::{Arg, CodeBlock, CodeBlockBuilder};
 use crate::poet::code_writer::CodeWriter;
-use crate::poet::DEFAULT_INDENT;
+use crate::poet::format_options::FormatOptions;
+use crate::poet::method_spec::MethodSpec;
+use crate::poet::property_spec::PropertySpec;
+use crate::poet::sink::{IoSink, NullSink};
+use crate::poet::type_alias_spec::TypeAliasSpec;
+use crate::poet::type_spec::TypeSpec;
+use crate::poet::{check_name, escape_if_necessary, escape_segments_if_necessary};
 use core::fmt;
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::{BufWriter, Write};
+use std::path::{Component, Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// The extension of generated source files.
+pub const FILE_EXTENSION: &str = "cj";
 
 ///
 /// A Kotlin file containing top level objects like classes, objects, functions, properties, and type
@@ -13,77 +31,425 @@ use core::fmt;
 /// - Imports
 /// - Members
 ///
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct FileSpec {
-    package_name: &'static str,
-    name: &'static str,
-    indent: &'static str,
+    comment: CodeBlock,
+    annotations: Vec<AnnotationSpec>,
+    package_name: String,
+    name: String,
+    options: FormatOptions,
+    members: Vec<FileMember>,
+}
+
+#[allow(clippy::large_enum_variant)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub enum FileMember {
+    Type(TypeSpec),
+    Method(MethodSpec),
+    Property(PropertySpec),
+    TypeAlias(TypeAliasSpec),
+}
+
+impl FileMember {
+    pub fn emit(&self, writer: &mut CodeWriter) {
+        match self {
+            FileMember::Type(type_spec) => type_spec.emit(writer),
+            FileMember::Method(method_spec) => method_spec.emit(writer),
+            FileMember::Property(property_spec) => property_spec.emit(writer, false),
+            FileMember::TypeAlias(type_alias_spec) => type_alias_spec.emit(writer),
+        }
+    }
 }
 
 impl FileSpec {
     pub fn new(builder: &mut FileSpecBuilder) -> Self {
         FileSpec {
-            package_name: builder.package_name,
-            name: builder.name,
-            indent: builder.indent,
+            comment: builder.comment.build(),
+            annotations: builder.annotations.clone(),
+            package_name: builder.package_name.clone(),
+            name: builder.name.clone(),
+            options: builder.options.clone(),
+            members: builder.members.clone(),
         }
     }
 
-    pub fn write_to(&self, _fmt: &mut fmt::Formatter) {
-        // todo: add logic
+    pub fn package_name(&self) -> &str {
+        &self.package_name
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    ///
+    /// Writes this file to `out` as it is generated, without building it in memory first.
+    ///
+    pub fn write_to(&self, out: &mut dyn fmt::Write) -> fmt::Result {
         // First pass: emit the entire class, just to collect the types we'll need to import.
+        let mut null_out = NullSink;
+        let mut import_collector = CodeWriter::with_options(&mut null_out, self.options.clone());
+        self.emit(&mut import_collector);
+        import_collector.close();
+        let suggested_imports = import_collector.suggested_imports();
+        let suggested_member_imports = import_collector.suggested_member_imports();
 
         // Second pass: write the code, taking advantage of the imports.
-        let mut out = "".to_string();
-        let mut writer = CodeWriter::new(&mut out, self.indent);
+        let mut out = TrailingNewlines { out, pending: 0 };
+        let mut writer = CodeWriter::with_options(&mut out, self.options.clone());
+        writer.import_types = suggested_imports;
+        writer.import_members = suggested_member_imports;
         self.emit(&mut writer);
         writer.close();
+        writer.result()?;
+        if self.options.final_newline {
+            out.flush()?;
+        }
+        Ok(())
+    }
+
+    ///
+    /// Writes this file to `out`, such as a file, socket or compressor, as it is generated.
+    ///
+    pub fn write_to_io<W: io::Write>(&self, out: W) -> io::Result<()> {
+        let mut sink = IoSink::new(out);
+        let result = self.write_to(&mut sink);
+        sink.finish()?;
+        result.map_err(|_| io::Error::other("formatting failed"))
+    }
+
+    ///
+    /// Returns where this file goes under `directory`: one directory per package name segment,
+    /// holding `<name>.cj`. Fails if a package segment or the file name is empty, `.` or `..`, or
+    /// contains a path separator, so the path can't escape `directory`.
+    ///
+    pub fn path_in(&self, directory: &Path) -> io::Result<PathBuf> {
+        let mut path = directory.to_path_buf();
+        if !self.package_name.is_empty() {
+            for segment in self.package_name.split('.') {
+                push_segment(&mut path, segment)?;
+            }
+        }
+        push_segment(&mut path, &format!("{}.{}", self.name, FILE_EXTENSION))?;
+        Ok(path)
+    }
+
+    ///
+    /// Writes this file under `directory`, creating its package directories as needed, and
+    /// returns the path written. The file is written to a temporary file first and renamed into
+    /// place, so readers never see it half written.
+    ///
+    pub fn write_to_dir(&self, directory: &Path) -> io::Result<PathBuf> {
+        let path = self.path_in(directory)?;
+        write_atomically(&path, |out| self.write_to_io(out))?;
+        Ok(path)
+    }
+
+    ///
+    /// Like [FileSpec::write_to_dir], but leaves the file alone if it already holds exactly this
+    /// content, so its modification time doesn't trigger rebuilds. Returns the path and what was
+    /// done to it.
+    ///
+    pub fn write_to_dir_if_changed(&self, directory: &Path) -> io::Result<(PathBuf, WriteOutcome)> {
+        let path = self.path_in(directory)?;
+        let content = self.to_string();
+        let outcome = match fs::read(&path) {
+            Ok(existing) if existing == content.as_bytes() => {
+                return Ok((path, WriteOutcome::Unchanged))
+            }
+            Ok(_) => WriteOutcome::Updated,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => WriteOutcome::Created,
+            Err(error) => return Err(error),
+        };
+        write_atomically(&path, |out| out.write_all(content.as_bytes()))?;
+        Ok((path, outcome))
     }
 
     pub fn emit(&self, writer: &mut CodeWriter) {
-        writer.push_package(self.package_name);
-        writer.emit_code("package·%L\n", vec![String::from(self.package_name)]);
-        // writer.emit("\n");
+        if !self.comment.is_empty() {
+            writer.emit_comment(&self.comment);
+        }
+
+        if !self.annotations.is_empty() {
+            writer.emit_annotations(&self.annotations, false);
+            writer.emit(String::from("\n"));
+        }
+
+        writer.push_package(&self.package_name);
+        if !self.package_name.is_empty() {
+            let package_name = escape_segments_if_necessary(&self.package_name);
+            writer.emit_code("package·%L\n", vec![package_name]);
+            writer.emit(String::from("\n"));
+        }
+
+        let mut imports: Vec<String> = writer
+            .import_types
+            .values()
+            .filter(|struct_name| struct_name.package_name != self.package_name)
+            .map(|struct_name| struct_name.canonical_name())
+            .collect();
+        imports.extend(writer.import_members.iter().map(|(alias, member_name)| {
+            if *alias == member_name.simple_name {
+                member_name.canonical_name()
+            } else {
+                format!(
+                    "{}·as·{}",
+                    member_name.canonical_name(),
+                    escape_if_necessary(alias)
+                )
+            }
+        }));
+        imports.sort();
+        for import in imports.iter() {
+            writer.emit_code("import·%L\n", vec![import.clone()]);
+        }
+        if !imports.is_empty() {
+            writer.emit(String::from("\n"));
+        }
+
+        for (index, member) in self.members.iter().enumerate() {
+            if index > 0 {
+                writer.emit_member_separator();
+            }
+            member.emit(writer);
+        }
+    }
+}
+
+///
+/// Appends `segment` to `path` as a single directory or file name, failing if it would be anything
+/// else, like `..` or a nested path.
+///
+fn push_segment(path: &mut PathBuf, segment: &str) -> io::Result<()> {
+    let mut components = Path::new(segment).components();
+    let is_name = matches!(components.next(), Some(Component::Normal(name)) if name == segment)
+        && components.next().is_none()
+        && !segment.contains(['/', '\\']);
+    if !is_name {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("invalid path segment {:?}", segment),
+        ));
+    }
+    path.push(segment);
+    Ok(())
+}
+
+/// Distinguishes the temporary files of concurrent writes to the same path within this process.
+static TEMP_FILE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+///
+/// Writes `path` through `write`, creating its parent directories as needed. The content goes to a
+/// temporary file next to `path`, unique to this write, that is synced to disk and then renamed
+/// into place once complete.
+///
+pub(crate) fn write_atomically<F>(path: &Path, write: F) -> io::Result<()>
+where
+    F: FnOnce(&mut BufWriter<File>) -> io::Result<()>,
+{
+    let directory = path.parent().unwrap_or_else(|| Path::new("."));
+    fs::create_dir_all(directory)?;
+
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let temp_path = directory.join(format!(
+        ".{}.{}.{}.tmp",
+        file_name,
+        process::id(),
+        TEMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let result = File::create(&temp_path)
+        .and_then(|file| {
+            let mut out = BufWriter::new(file);
+            write(&mut out)?;
+            let file = out.into_inner().map_err(|error| error.into_error())?;
+            file.sync_all()
+        })
+        .and_then(|_| fs::rename(&temp_path, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+/// What writing a file to a directory did to it.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum WriteOutcome {
+    Created,
+    Updated,
+    Unchanged,
+}
+
+/// Counts of the [WriteOutcome]s of writing a set of files.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct WriteSummary {
+    pub created: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+    /// Stale files removed because they are no longer generated.
+    pub deleted: usize,
+}
+
+impl WriteSummary {
+    pub fn record(&mut self, outcome: WriteOutcome) {
+        match outcome {
+            WriteOutcome::Created => self.created += 1,
+            WriteOutcome::Updated => self.updated += 1,
+            WriteOutcome::Unchanged => self.unchanged += 1,
+        }
+    }
+}
+
+///
+/// Holds back newlines until more text follows them, so a file's trailing newlines can be dropped
+/// without buffering the file.
+///
+struct TrailingNewlines<'a> {
+    out: &'a mut dyn fmt::Write,
+    pending: usize,
+}
+
+impl TrailingNewlines<'_> {
+    fn flush(&mut self) -> fmt::Result {
+        for _ in 0..self.pending {
+            self.out.write_char('\n')?;
+        }
+        self.pending = 0;
+        Ok(())
+    }
+}
+
+impl fmt::Write for TrailingNewlines<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let text = s.trim_end_matches('\n');
+        if !text.is_empty() {
+            self.flush()?;
+            self.out.write_str(text)?;
+        }
+        self.pending += s.len() - text.len();
+        Ok(())
     }
 }
 
 impl fmt::Display for FileSpec {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        &self.write_to(fmt);
-        Ok(())
+        self.write_to(fmt)
     }
 }
 
 #[derive(Serialize, Debug)]
 pub struct FileSpecBuilder {
-    package_name: &'static str,
-    name: &'static str,
-    indent: &'static str,
+    comment: CodeBlockBuilder,
+    annotations: Vec<AnnotationSpec>,
+    package_name: String,
+    name: String,
+    options: FormatOptions,
+    members: Vec<FileMember>,
 }
 
 impl FileSpecBuilder {
-    pub fn new(package_name: &'static str, file_name: &'static str) -> Self {
+    pub fn new(package_name: impl Into<String>, file_name: impl Into<String>) -> Self {
         FileSpecBuilder {
-            package_name,
-            name: file_name,
-            indent: DEFAULT_INDENT,
+            comment: CodeBlockBuilder::new(),
+            annotations: vec![],
+            package_name: package_name.into(),
+            name: file_name.into(),
+            options: FormatOptions::default(),
+            members: vec![],
         }
     }
 
-    pub fn add_file_comment(&self, _format: String) {}
+    ///
+    /// Adds a comment written above the package line, such as a license header. `format` accepts
+    /// the same placeholders as `CodeBlock`.
+    ///
+    pub fn add_file_comment<T: Into<Arg>>(
+        &mut self,
+        format: &str,
+        args: Vec<T>,
+    ) -> &mut FileSpecBuilder {
+        self.comment.add(format, args);
+        self
+    }
+
+    ///
+    /// Adds an annotation on the file itself. Its use-site target defaults to `file`; no other
+    /// target is allowed.
+    ///
+    pub fn add_annotation(&mut self, annotation: AnnotationSpec) -> &mut FileSpecBuilder {
+        let mut annotation = annotation;
+        match &annotation.use_site_target {
+            None => annotation.use_site_target = Some(UseSiteTarget::FILE),
+            Some(UseSiteTarget::FILE) => {}
+            Some(use_site_target) => panic!(
+                "use-site target {:?} not supported for file annotations",
+                use_site_target
+            ),
+        }
+        self.annotations.push(annotation);
+        self
+    }
 
     pub fn add_import(&self) {}
 
-    pub fn add_type_alias(&self) {}
+    pub fn add_type_alias(&mut self, type_alias_spec: TypeAliasSpec) -> &mut FileSpecBuilder {
+        self.members.push(FileMember::TypeAlias(type_alias_spec));
+        self
+    }
+
+    pub fn format_options(&mut self, options: FormatOptions) -> &mut FileSpecBuilder {
+        self.options = options;
+        self
+    }
+
+    pub fn add_type(&mut self, type_spec: TypeSpec) -> &mut FileSpecBuilder {
+        self.members.push(FileMember::Type(type_spec));
+        self
+    }
+
+    pub fn add_method(&mut self, method_spec: MethodSpec) -> &mut FileSpecBuilder {
+        if method_spec.is_constructor() {
+            panic!("cannot add a constructor to a file");
+        }
+        self.members.push(FileMember::Method(method_spec));
+        self
+    }
+
+    pub fn add_property(&mut self, property_spec: PropertySpec) -> &mut FileSpecBuilder {
+        self.members.push(FileMember::Property(property_spec));
+        self
+    }
 
     pub fn build(&mut self) -> FileSpec {
+        if !self.package_name.is_empty() {
+            self.package_name.split('.').for_each(check_name);
+        }
         FileSpec::new(self)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::poet::file_spec::FileSpecBuilder;
+    use crate::poet::annotation_spec::{AnnotationSpec, UseSiteTarget};
+    use crate::poet::class_name::StructName;
+    use crate::poet::code_block::Arg;
+    use crate::poet::code_writer::CodeWriter;
+    use crate::poet::file_spec::{FileSpec, FileSpecBuilder, WriteOutcome, WriteSummary};
+    use crate::poet::format_options::{BraceStyle, FormatOptions};
+    use crate::poet::member_name::MemberName;
+    use crate::poet::method_spec::MethodSpec;
+    use crate::poet::operator::Operator;
+    use crate::poet::parameter_spec::ParameterSpec;
+    use crate::poet::property_spec::PropertySpec;
+    use crate::poet::type_alias_spec::TypeAliasSpec;
+    use crate::poet::type_name::TypeName;
+    use crate::poet::type_spec::TypeSpec;
+    use crate::poet::DEFAULT_INDENT;
+    use std::{env, fs, io, process};
+
+    #[test]
+    #[should_panic(expected = "can't escape identifier \"exam<ple\": it contains '<'")]
+    fn invalid_package_segment() {
+        FileSpecBuilder::new("com.exam<ple", "Taco").build();
+    }
 
     #[test]
     fn should_call_builder() {
@@ -92,4 +458,543 @@ mod tests {
         println!("{:?}", spec);
         assert_eq!("com.phodal", spec.package_name);
     }
+
+    #[test]
+    fn imports_referenced_types() {
+        let constructor = MethodSpec::constructor_builder()
+            .add_parameter(
+                ParameterSpec::builder("fillings", TypeName::get("com.phodal.food", "Filling"))
+                    .build(),
+            )
+            .add_parameter(
+                ParameterSpec::builder("shell", TypeName::get("com.phodal", "Shell")).build(),
+            )
+            .build();
+        let taco = TypeSpec::class_builder("Taco")
+            .primary_constructor(constructor)
+            .build();
+        let spec = FileSpecBuilder::new("com.phodal", "Taco")
+            .add_type(taco)
+            .build();
+
+        assert_eq!(
+            "package com.phodal
+
+import com.phodal.food.Filling
+
+class Taco(fillings: Filling, shell: Shell)
+",
+            format!("{}", spec)
+        );
+    }
+
+    #[test]
+    fn conflicting_simple_names_are_qualified() {
+        let constructor = MethodSpec::constructor_builder()
+            .add_parameter(ParameterSpec::builder("a", TypeName::get("com.a", "Taco")).build())
+            .add_parameter(ParameterSpec::builder("b", TypeName::get("com.b", "Taco")).build())
+            .build();
+        let spec = FileSpecBuilder::new("com.phodal", "Tacos")
+            .add_type(
+                TypeSpec::class_builder("Tacos")
+                    .primary_constructor(constructor)
+                    .build(),
+            )
+            .build();
+
+        assert_eq!(
+            "package com.phodal\n\nclass Tacos(a: com.a.Taco, b: com.b.Taco)\n",
+            format!("{}", spec)
+        );
+    }
+
+    #[test]
+    fn extension_function_imports_receiver() {
+        let shout = MethodSpec::builder("shout")
+            .receiver(TypeName::get("com.phodal.text", "Line"))
+            .returns(TypeName::get("com.phodal.text", "Line"))
+            .add_statement("return %L", vec!["this"])
+            .build();
+        let spec = FileSpecBuilder::new("com.phodal", "Extensions")
+            .add_method(shout)
+            .build();
+
+        assert_eq!(
+            "package com.phodal
+
+import com.phodal.text.Line
+
+fun Line.shout(): Line {
+    return this
+}
+",
+            format!("{}", spec)
+        );
+    }
+
+    #[test]
+    fn extension_member_is_imported() {
+        let shout = MethodSpec::builder("shout")
+            .receiver(TypeName::get("", "String"))
+            .build()
+            .member_name("com.phodal.text");
+        let main = MethodSpec::builder("main")
+            .add_statement("%S.%M()", vec![Arg::from("taco"), Arg::from(shout)])
+            .build();
+        let spec = FileSpecBuilder::new("com.phodal", "Main")
+            .add_method(main)
+            .build();
+
+        assert_eq!(
+            "package com.phodal
+
+import com.phodal.text.shout
+
+fun main() {
+    \"taco\".shout()
+}
+",
+            format!("{}", spec)
+        );
+    }
+
+    #[test]
+    fn clashing_extension_members_are_imported_with_alias() {
+        let text_shout = MemberName::extension("com.phodal.text", "shout");
+        let loud_shout = MemberName::extension("com.phodal.loud", "shout");
+        let main = MethodSpec::builder("main")
+            .add_statement("%S.%M()", vec![Arg::from("taco"), Arg::from(text_shout)])
+            .add_statement("%S.%M()", vec![Arg::from("salsa"), Arg::from(loud_shout)])
+            .build();
+        let spec = FileSpecBuilder::new("com.phodal", "Main")
+            .add_method(main)
+            .build();
+
+        assert_eq!(
+            "package com.phodal
+
+import com.phodal.loud.shout as shout3
+import com.phodal.text.shout as shout2
+
+fun main() {
+    \"taco\".shout2()
+    \"salsa\".shout3()
+}
+",
+            format!("{}", spec)
+        );
+    }
+
+    #[test]
+    fn operator_member_renders_operator() {
+        let plus = MemberName::with_operator("com.phodal.ops", Operator::PLUS);
+        let main = MethodSpec::builder("main")
+            .add_statement(
+                "val c = %N %M %N",
+                vec![Arg::from("a"), Arg::from(plus), Arg::from("b")],
+            )
+            .build();
+        let spec = FileSpecBuilder::new("com.phodal", "Main")
+            .add_method(main)
+            .build();
+
+        assert_eq!(
+            "package com.phodal
+
+import com.phodal.ops.plus
+
+fun main() {
+    val c = a + b
+}
+",
+            format!("{}", spec)
+        );
+    }
+
+    #[test]
+    fn type_alias_reference_imports_alias() {
+        let alias = TypeAliasSpec::builder("Word", TypeName::get("", "String")).build();
+        let aliases = FileSpecBuilder::new("com.phodal.words", "Aliases")
+            .add_type_alias(alias.clone())
+            .build();
+        assert_eq!(
+            "package com.phodal.words\n\ntypealias Word = String\n",
+            format!("{}", aliases)
+        );
+
+        let shout = MethodSpec::builder("shout")
+            .add_parameter(
+                ParameterSpec::builder("word", alias.struct_name("com.phodal.words").into())
+                    .build(),
+            )
+            .build();
+        let spec = FileSpecBuilder::new("com.phodal", "Shout")
+            .add_method(shout)
+            .build();
+        assert_eq!(
+            "package com.phodal
+
+import com.phodal.words.Word
+
+fun shout(word: Word) {
+}
+",
+            format!("{}", spec)
+        );
+    }
+
+    #[test]
+    fn file_comment_and_annotations() {
+        let spec = FileSpecBuilder::new("com.phodal", "Taco")
+            .add_file_comment("Generated by %L, do not edit.\n", vec!["poet"])
+            .add_file_comment("\nCopyright %L Phodal", vec!["2020"])
+            .add_annotation(
+                AnnotationSpec::builder(StructName::new("kotlin.jvm", "JvmName"))
+                    .add_member("%S", vec!["Tacos"])
+                    .build(),
+            )
+            .add_type(TypeSpec::object_builder("Taco").build())
+            .build();
+
+        assert_eq!(
+            "// Generated by poet, do not edit.
+//
+// Copyright 2020 Phodal
+@file:JvmName(\"Tacos\")
+
+package com.phodal
+
+import kotlin.jvm.JvmName
+
+object Taco
+",
+            format!("{}", spec)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "use-site target FIELD not supported for file annotations")]
+    fn file_annotations_target_the_file() {
+        FileSpecBuilder::new("com.phodal", "Taco").add_annotation(
+            AnnotationSpec::builder(StructName::new("", "Inject"))
+                .use_site_target(UseSiteTarget::FIELD)
+                .build(),
+        );
+    }
+
+    #[test]
+    fn format_options() {
+        let taco = TypeSpec::class_builder("Taco")
+            .add_property(
+                PropertySpec::builder("filling", TypeName::get("", "String"))
+                    .initializer("%S", vec!["beef"])
+                    .build(),
+            )
+            .add_method(
+                MethodSpec::builder("eat")
+                    .add_statement("println(%N)", vec!["filling"])
+                    .build(),
+            )
+            .build();
+        let options = FormatOptions {
+            indent: String::from("  "),
+            blank_lines_between_members: 0,
+            brace_style: BraceStyle::NextLine,
+            final_newline: false,
+            ..FormatOptions::default()
+        };
+        let spec = FileSpecBuilder::new("com.phodal", "Taco")
+            .format_options(options)
+            .add_type(taco)
+            .build();
+
+        assert_eq!(
+            "package com.phodal
+
+class Taco
+{
+  val filling: String = \"beef\"
+  fun eat()
+  {
+    println(filling)
+  }
+}",
+            format!("{}", spec)
+        );
+    }
+
+    #[test]
+    fn column_limit() {
+        let arguments = ["taco"; 8].join(", ");
+        let main = MethodSpec::builder("main")
+            .add_statement("eat(%L)", vec![arguments])
+            .build();
+        let spec = FileSpecBuilder::new("", "Main")
+            .format_options(FormatOptions {
+                column_limit: 40,
+                ..FormatOptions::default()
+            })
+            .add_method(main)
+            .build();
+
+        assert_eq!(
+            "fun main() {
+    eat(taco, taco, taco, taco, taco,
+            taco, taco, taco)
+}
+",
+            format!("{}", spec)
+        );
+    }
+
+    #[test]
+    fn write_to_io_streams_the_file() {
+        let spec = FileSpecBuilder::new("com.example", "Taco")
+            .add_type(TypeSpec::class_builder("Taco").build())
+            .build();
+
+        let mut out: Vec<u8> = vec![];
+        spec.write_to_io(&mut out).unwrap();
+
+        assert_eq!(spec.to_string(), String::from_utf8(out).unwrap());
+        assert_eq!("package com.example\n\nclass Taco\n", spec.to_string());
+    }
+
+    #[test]
+    fn write_to_dir() {
+        let directory = env::temp_dir().join(format!("charjpoet-write-to-dir-{}", process::id()));
+        let spec = FileSpecBuilder::new("com.example.food", "Taco")
+            .add_type(TypeSpec::class_builder("Taco").build())
+            .build();
+
+        let path = spec.write_to_dir(&directory).unwrap();
+
+        assert_eq!(directory.join("com/example/food/Taco.cj"), path);
+        assert_eq!(spec.to_string(), fs::read_to_string(&path).unwrap());
+        let entries = fs::read_dir(path.parent().unwrap()).unwrap().count();
+        assert_eq!(1, entries, "temporary file left behind");
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn write_to_dir_without_package() {
+        let directory = env::temp_dir().join(format!("charjpoet-no-package-{}", process::id()));
+        let spec = FileSpecBuilder::new("", "Main").build();
+
+        let path = spec.write_to_dir(&directory).unwrap();
+
+        assert_eq!(directory.join("Main.cj"), path);
+        assert!(path.exists());
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn write_to_dir_rejects_escaping_paths() {
+        let directory = env::temp_dir().join(format!("charjpoet-escape-{}", process::id()));
+        let escaping = [
+            ("com..example", "Taco"),
+            ("com.example", "../../Taco"),
+            ("com.example", "food/Taco"),
+            ("com/example", "Taco"),
+            ("..", "Taco"),
+            ("com.", "Taco"),
+        ];
+        for (package_name, name) in escaping.iter() {
+            // Deserialized specs skip the builder's checks, so path_in checks the segments again.
+            let mut spec = FileSpecBuilder::new("com.example", "Taco").build();
+            spec.package_name = String::from(*package_name);
+            spec.name = String::from(*name);
+
+            let error = spec.path_in(&directory).unwrap_err();
+            assert_eq!(io::ErrorKind::InvalidInput, error.kind());
+            assert!(spec.write_to_dir(&directory).is_err());
+        }
+        assert!(!directory.exists());
+    }
+
+    #[test]
+    fn write_to_dir_if_changed() {
+        let directory = env::temp_dir().join(format!("charjpoet-if-changed-{}", process::id()));
+        let taco = |kind: &str| {
+            FileSpecBuilder::new("com.example", "Taco")
+                .add_type(TypeSpec::class_builder(kind).build())
+                .build()
+        };
+        let mut summary = WriteSummary::default();
+
+        let (path, outcome) = taco("Taco").write_to_dir_if_changed(&directory).unwrap();
+        summary.record(outcome);
+        assert_eq!(WriteOutcome::Created, outcome);
+
+        let modified = fs::metadata(&path).unwrap().modified().unwrap();
+        let (_, outcome) = taco("Taco").write_to_dir_if_changed(&directory).unwrap();
+        summary.record(outcome);
+        assert_eq!(WriteOutcome::Unchanged, outcome);
+        assert_eq!(modified, fs::metadata(&path).unwrap().modified().unwrap());
+
+        let (_, outcome) = taco("Burrito").write_to_dir_if_changed(&directory).unwrap();
+        summary.record(outcome);
+        assert_eq!(WriteOutcome::Updated, outcome);
+        assert_eq!(
+            taco("Burrito").to_string(),
+            fs::read_to_string(&path).unwrap()
+        );
+
+        assert_eq!(
+            WriteSummary {
+                created: 1,
+                updated: 1,
+                unchanged: 1,
+                deleted: 0
+            },
+            summary
+        );
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    fn lunch() -> FileSpec {
+        let mut main = MethodSpec::builder("main");
+        for (index, package) in ["com.squareup", "org.example", "net.tacos", "io.food"]
+            .iter()
+            .enumerate()
+        {
+            for name in ["Taco", "Burrito", "Salsa", "Queso", "Horchata"] {
+                let type_name = StructName::new(*package, format!("{}{}", name, index));
+                let member = MemberName::new(*package, format!("eat{}{}", name, index));
+                main.add_statement("%T().%M()", vec![Arg::from(type_name), Arg::from(member)]);
+            }
+            let shout = MemberName::extension(*package, "shout");
+            main.add_statement("%S.%M()", vec![Arg::from("taco"), Arg::from(shout)]);
+        }
+        FileSpecBuilder::new("com.example", "Lunch")
+            .add_method(main.build())
+            .build()
+    }
+
+    #[test]
+    fn rendered_file_is_deterministic() {
+        let expected = lunch().to_string();
+        for _ in 0..50 {
+            assert_eq!(expected, lunch().to_string());
+        }
+
+        let imports: Vec<&str> = expected
+            .lines()
+            .filter(|line| line.starts_with("import "))
+            .collect();
+        assert_eq!(44, imports.len());
+        assert!(imports.windows(2).all(|pair| pair[0] < pair[1]));
+        for import in [
+            "import com.squareup.shout as shout2",
+            "import io.food.shout as shout5",
+            "import net.tacos.shout as shout4",
+            "import org.example.shout as shout3",
+        ] {
+            assert!(imports.contains(&import), "missing {}", import);
+        }
+    }
+
+    #[test]
+    fn collected_imports_are_deterministic() {
+        let collect = || {
+            let mut out = String::new();
+            let mut writer = CodeWriter::new(&mut out, DEFAULT_INDENT);
+            lunch().emit(&mut writer);
+            writer.close();
+            let importable =
+                serde_json::to_string(&(&writer.importable_types, &writer.importable_members))
+                    .unwrap();
+            let members: Vec<String> = writer.suggested_member_imports().into_keys().collect();
+            (importable, members)
+        };
+
+        let expected = collect();
+        for _ in 0..50 {
+            assert_eq!(expected, collect());
+        }
+
+        let (_, members) = expected;
+        assert_eq!(24, members.len());
+        assert!(members.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn serialized_name_allocator_is_deterministic() {
+        let serialize = || {
+            let mut main = MethodSpec::builder("main");
+            for name in ["taco", "burrito", "salsa", "queso", "horchata", "taco"] {
+                main.new_local(name);
+            }
+            serde_json::to_string(&main.name_allocator).unwrap()
+        };
+
+        let expected = serialize();
+        for _ in 0..50 {
+            assert_eq!(expected, serialize());
+        }
+    }
+
+    #[test]
+    fn names_known_only_at_runtime() {
+        let schema = serde_json::json!({ "package": "com.example.food", "type": "Taco" });
+        let package_name = schema["package"].as_str().unwrap().to_string();
+        let type_name = schema["type"].as_str().unwrap().to_string();
+        let spec = FileSpecBuilder::new(package_name.clone(), type_name.clone())
+            .add_type(TypeSpec::class_builder(type_name).build())
+            .build();
+        drop(schema);
+
+        assert_eq!(package_name, spec.package_name());
+        assert_eq!("package com.example.food\n\nclass Taco\n", spec.to_string());
+    }
+
+    #[test]
+    fn deserialize_round_trip() {
+        let spec = lunch();
+        let json = serde_json::to_string(&spec).unwrap();
+        let deserialized: FileSpec = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(spec.to_string(), deserialized.to_string());
+    }
+
+    #[test]
+    fn unsafe_line_starts() {
+        let main = MethodSpec::builder("main")
+            .add_statement("val total = %L", vec!["taco + taco + taco"])
+            .build();
+        let options = FormatOptions {
+            column_limit: 28,
+            ..FormatOptions::default()
+        };
+        let spec = FileSpecBuilder::new("", "Main")
+            .format_options(options.clone())
+            .add_method(main.clone())
+            .build();
+
+        assert_eq!(
+            "fun main() {
+    val total = taco +
+            taco + taco
+}
+",
+            format!("{}", spec)
+        );
+
+        let spec = FileSpecBuilder::new("", "Main")
+            .format_options(FormatOptions {
+                unsafe_line_starts: vec![],
+                ..options
+            })
+            .add_method(main)
+            .build();
+
+        assert_eq!(
+            "fun main() {
+    val total = taco + taco
+            + taco
+}
+",
+            format!("{}", spec)
+        );
+    }
 }
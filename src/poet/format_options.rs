@@ -0,0 +1,57 @@
+use crate::poet::line_wrapper::UNSAFE_LINE_STARTS;
+use crate::poet::DEFAULT_INDENT;
+
+/// Where the opening brace of a body goes.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum BraceStyle {
+    /// `fun taco() {`
+    SameLine,
+    /// The brace on a line of its own, below the declaration.
+    NextLine,
+}
+
+///
+/// Settings that control the layout of generated code, so output can match a house style. The
+/// defaults follow the Kotlin coding conventions:
+///
+/// ```
+/// use charjpoet::poet::format_options::FormatOptions;
+///
+/// let options = FormatOptions {
+///     column_limit: 120,
+///     indent: String::from("  "),
+///     ..FormatOptions::default()
+/// };
+/// assert!(options.final_newline);
+/// ```
+///
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct FormatOptions {
+    /// Lines longer than this are wrapped at their wrapping spaces.
+    pub column_limit: i32,
+    /// One level of indentation.
+    pub indent: String,
+    /// Whether lists written one element per line end with a comma after the last element.
+    pub trailing_commas: bool,
+    /// Blank lines emitted between the members of a file or type body.
+    pub blank_lines_between_members: usize,
+    pub brace_style: BraceStyle,
+    /// Whether a file ends with a newline.
+    pub final_newline: bool,
+    /// Tokens that must not begin a wrapped line, so wrapping never changes what code means.
+    pub unsafe_line_starts: Vec<String>,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        FormatOptions {
+            column_limit: 100,
+            indent: String::from(DEFAULT_INDENT),
+            trailing_commas: true,
+            blank_lines_between_members: 1,
+            brace_style: BraceStyle::SameLine,
+            final_newline: true,
+            unsafe_line_starts: UNSAFE_LINE_STARTS.iter().map(|t| t.to_string()).collect(),
+        }
+    }
+}
@@ -1,5 +1,5 @@
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Import {
-    pub qualified_name: &'static str,
+    pub qualified_name: String,
     pub alias: Option<String>,
 }
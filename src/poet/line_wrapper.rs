@@ -1,29 +1,58 @@
-use crate::poet::index_of_any;
-use regex::Regex;
-use std::fmt::Write;
+use crate::poet::{display_width, index_of_any};
+use std::fmt;
 
 pub const SPECIAL_CHARACTERS: [char; 3] = [' ', '\n', '·'];
-lazy_static! {
-    static ref UNSAFE_LINE_START: Regex = Regex::new(r"\\s*[-+].*").unwrap();
-}
+
+///
+/// Tokens that must not begin a wrapped line. Charj statements are terminated by a line break
+/// rather than a semicolon, so a line that is complete on its own ends the statement: breaking
+/// before a binary operator would change `a\n- b` into two statements. Word tokens like `as` only
+/// match as whole words.
+///
+pub const UNSAFE_LINE_STARTS: [&str; 22] = [
+    "+", "-", "*", "/", "%", "==", "!=", "<", ">", "<=", ">=", "=", "&&", "||", "?:", "..", ".",
+    "?.", "as", "as?", "is", "!is",
+];
 
 ///
 /// Implements soft line wrapping on an appendable. To use, append characters using {@link #append}
-/// or soft-wrapping spaces using {@link #wrappingSpace}.
+/// or soft-wrapping spaces using {@link #wrappingSpace}. Lines are written to `out` as they are
+/// completed, so output can stream to any [fmt::Write] sink.
 ///
-#[derive(Serialize, Debug)]
+#[derive(Serialize)]
 pub struct LineWrapper<'a> {
-    pub out: &'a mut String,
+    #[serde(skip)]
+    pub out: &'a mut dyn fmt::Write,
     pub indent: String,
     pub column_limit: i32,
     pub segments: Vec<String>,
     pub indent_level: i32,
     pub line_prefix: String,
+    /// Segments starting with one of these are kept on the line before them.
+    pub unsafe_line_starts: Vec<String>,
     closed: bool,
+    /// The first error `out` returned. Nothing more is written after one.
+    #[serde(skip)]
+    result: fmt::Result,
+}
+
+impl fmt::Debug for LineWrapper<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LineWrapper")
+            .field("indent", &self.indent)
+            .field("column_limit", &self.column_limit)
+            .field("segments", &self.segments)
+            .field("indent_level", &self.indent_level)
+            .field("line_prefix", &self.line_prefix)
+            .field("unsafe_line_starts", &self.unsafe_line_starts)
+            .field("closed", &self.closed)
+            .field("result", &self.result)
+            .finish()
+    }
 }
 
 impl<'a> LineWrapper<'a> {
-    pub fn new(out: &'a mut String, indent: String, column_limit: i32) -> Self {
+    pub fn new(out: &'a mut dyn fmt::Write, indent: String, column_limit: i32) -> Self {
         LineWrapper {
             out,
             indent,
@@ -31,10 +60,18 @@ impl<'a> LineWrapper<'a> {
             segments: vec!["".to_string()],
             indent_level: -1,
             line_prefix: "".to_string(),
+            unsafe_line_starts: UNSAFE_LINE_STARTS.iter().map(|t| t.to_string()).collect(),
             closed: false,
+            result: Ok(()),
         }
     }
 
+    /// Returns the column the next character goes to if the pending line isn't wrapped.
+    pub fn current_column(&self) -> usize {
+        let width: usize = self.segments.iter().map(|s| display_width(s)).sum();
+        width + self.segments.len() - 1
+    }
+
     pub fn has_pending_segments(&self) -> bool {
         self.segments.len() != 1 || !self.segments[0].is_empty()
     }
@@ -47,17 +84,8 @@ impl<'a> LineWrapper<'a> {
     ) {
         let mut pos: usize = 0;
 
-        let indent_level: i32;
-        match _indent_level {
-            None => indent_level = -1,
-            Some(level) => indent_level = level,
-        }
-
-        let line_prefix: String;
-        match _line_prefix {
-            None => line_prefix = "".to_string(),
-            Some(prefix) => line_prefix = prefix,
-        }
+        let indent_level = _indent_level.unwrap_or(-1);
+        let line_prefix = _line_prefix.unwrap_or_default();
 
         let chars: Vec<char> = input.chars().collect();
         while pos < chars.len() {
@@ -67,26 +95,26 @@ impl<'a> LineWrapper<'a> {
                     self.indent_level = indent_level;
                     self.line_prefix = line_prefix.clone();
                     self.segments.push(String::from(""));
-                    pos = pos + 1;
+                    pos += 1;
                 }
                 '\n' => {
                     self.new_line();
-                    pos = pos + 1;
+                    pos += 1;
                 }
                 '·' => {
                     let len = self.segments.len();
                     self.segments[len - 1].push(' ');
-                    pos = pos + 1;
+                    pos += 1;
                 }
                 _ => {
-                    let mut next = index_of_any(&*chars, SPECIAL_CHARACTERS, pos);
+                    let mut next = index_of_any(&chars, &SPECIAL_CHARACTERS, pos);
                     if next == -1 {
                         next = chars.len() as i32;
                     }
 
                     let len = self.segments.len();
                     let i = next as usize - pos;
-                    let others: String = input.chars().skip(pos).take(i as usize).collect();
+                    let others: String = input.chars().skip(pos).take(i).collect();
                     self.segments[len - 1].push_str(others.as_str());
                     pos = next as usize;
                 }
@@ -103,34 +131,57 @@ impl<'a> LineWrapper<'a> {
 
     pub fn check(&mut self) {}
 
-    #[allow(unused_must_use)]
     pub fn new_line(&mut self) {
         self.check();
 
         self.emit_current_line();
-        write!(self.out, "\n");
+        self.write("\n");
         self.indent_level = -1
     }
 
+    fn write(&mut self, s: &str) {
+        if self.result.is_ok() {
+            self.result = self.out.write_str(s);
+        }
+    }
+
+    /// Returns the first error the sink returned, if any.
+    pub fn result(&self) -> fmt::Result {
+        self.result
+    }
+
+    ///
+    /// Returns true if `segment` starts with one of the unsafe line start tokens. A token ending
+    /// in a letter must not be followed by an identifier character, so `as` doesn't match `assert`.
+    ///
+    fn is_unsafe_line_start(&self, segment: &str) -> bool {
+        self.unsafe_line_starts
+            .iter()
+            .any(|token| match segment.strip_prefix(token.as_str()) {
+                None => false,
+                Some(rest) => {
+                    let is_word = token.ends_with(|c: char| c.is_alphanumeric() || c == '_');
+                    !is_word || !rest.starts_with(|c: char| c.is_alphanumeric() || c == '_')
+                }
+            })
+    }
+
     /**
-     * Any segment that starts with '+' or '-' can't have a break preceding it. Combine it with the
-     * preceding segment. Note that this doesn't apply to the first segment.
+     * Any segment that starts with an unsafe line start token, like '+' or '-', can't have a
+     * break preceding it. Combine it with the preceding segment. Note that this doesn't apply to
+     * the first segment. Folding doesn't change how the preceding segment starts, so it is never
+     * folded again in turn.
      */
-    #[allow(unused_must_use)]
     fn fold_unsafe_breaks(&mut self) {
         let mut i = 1;
         while i < self.segments.len() {
             let segment = &self.segments[i];
-            if UNSAFE_LINE_START.is_match(segment) {
-                write!(self.segments[i - 1], " ");
-                let next = self.segments[i].clone();
-                write!(self.segments[i - 1], "{}", next);
-                self.segments.remove(i);
-                if i > 1 {
-                    i = i - 1;
-                }
+            if self.is_unsafe_line_start(segment) {
+                let next = self.segments.remove(i);
+                self.segments[i - 1].push(' ');
+                self.segments[i - 1].push_str(&next);
             } else {
-                i = i + 1;
+                i += 1;
             }
         }
     }
@@ -138,17 +189,18 @@ impl<'a> LineWrapper<'a> {
         self.fold_unsafe_breaks();
 
         let mut start = 0;
-        let mut column_count = self.segments[0].len() as i32;
+        let mut column_count = display_width(&self.segments[0]) as i32;
 
         for i in 1..self.segments.len() {
             let segment = &self.segments[i];
-            let current_length = segment.len() as i32;
+            let current_length = display_width(segment) as i32;
 
-            let new_column_count = column_count + 1 + segment.len() as i32;
+            let new_column_count = column_count + 1 + current_length;
             if new_column_count > self.column_limit {
                 self.emit_segment_range(start, i as i32);
                 start = i as i32;
-                column_count = current_length + self.indent.len() as i32 * self.indent_level;
+                let indent_width = display_width(&self.indent) as i32;
+                column_count = current_length + indent_width * self.indent_level;
                 continue;
             }
 
@@ -161,21 +213,22 @@ impl<'a> LineWrapper<'a> {
         self.segments.push("".to_string());
     }
 
-    #[allow(unused_must_use)]
     pub fn emit_segment_range(&mut self, start_index: i32, end_index: i32) {
+        let mut line = String::new();
         if start_index > 0 {
-            write!(self.out, "\n");
+            line.push('\n');
             for _i in 0..self.indent_level {
-                write!(self.out, "{}", self.indent);
+                line.push_str(&self.indent);
             }
-            write!(self.out, "{}", self.line_prefix);
+            line.push_str(&self.line_prefix);
         }
 
-        write!(self.out, "{}", self.segments[start_index as usize]);
+        line.push_str(&self.segments[start_index as usize]);
         for i in start_index + 1..end_index {
-            write!(self.out, " ");
-            write!(self.out, "{}", self.segments[i as usize]);
+            line.push(' ');
+            line.push_str(&self.segments[i as usize]);
         }
+        self.write(&line);
     }
 
     pub fn close(&mut self) {
@@ -231,6 +284,38 @@ mod tests {
         assert_eq!("abcdefghij\n    klmnop", out);
     }
 
+    #[test]
+    fn wrap_wide_characters() {
+        let mut out = String::new();
+        let mut wrapper = LineWrapper::new(&mut out, String::from("  "), 10);
+        wrapper.append(String::from("一二 三四五"), Some(2), None);
+        wrapper.close();
+
+        assert_eq!("一二\n    三四五", out);
+    }
+
+    #[test]
+    fn nowrap_wide_characters() {
+        let mut out = String::new();
+        let mut wrapper = LineWrapper::new(&mut out, String::from("  "), 10);
+        wrapper.append(String::from("一二 三四e\u{301}"), Some(2), None);
+        wrapper.close();
+
+        assert_eq!("一二 三四e\u{301}", out);
+    }
+
+    #[test]
+    fn fencepost_wide_characters() {
+        let mut out = String::new();
+        let mut wrapper = LineWrapper::new(&mut out, String::from("  "), 10);
+        wrapper.append(String::from("一二"), Some(2), None);
+        wrapper.append(String::from("三四五 k"), Some(2), None);
+        wrapper.append(String::from("lmnop"), Some(2), None);
+        wrapper.close();
+
+        assert_eq!("一二三四五\n    klmnop", out);
+    }
+
     #[test]
     fn overly_long_lines_without_leading_space() {
         let mut out = String::new();
@@ -311,6 +396,47 @@ mod tests {
         assert_eq!("a - b     \n     - c", out);
     }
 
+    #[test]
+    fn no_wrap_preceding_binary_operators() {
+        let mut out = String::new();
+        let mut wrapper = LineWrapper::new(&mut out, String::from("  "), 10);
+        wrapper.append(String::from("aaaa && bbbbb || ccccc"), Some(2), None);
+        wrapper.close();
+
+        assert_eq!("aaaa &&\n    bbbbb ||\n    ccccc", out);
+    }
+
+    #[test]
+    fn no_wrap_preceding_member_access_and_cast() {
+        let mut out = String::new();
+        let mut wrapper = LineWrapper::new(&mut out, String::from("  "), 10);
+        wrapper.append(String::from("taco ?.shell as Shell"), Some(2), None);
+        wrapper.close();
+
+        assert_eq!("taco ?.shell as\n    Shell", out);
+    }
+
+    #[test]
+    fn wrap_preceding_word_starting_with_token() {
+        let mut out = String::new();
+        let mut wrapper = LineWrapper::new(&mut out, String::from("  "), 10);
+        wrapper.append(String::from("taco assert"), Some(2), None);
+        wrapper.close();
+
+        assert_eq!("taco\n    assert", out);
+    }
+
+    #[test]
+    fn custom_unsafe_line_starts() {
+        let mut out = String::new();
+        let mut wrapper = LineWrapper::new(&mut out, String::from("  "), 10);
+        wrapper.unsafe_line_starts = vec![String::from("|>")];
+        wrapper.append(String::from("aaaaa |> bbbbb - c"), Some(2), None);
+        wrapper.close();
+
+        assert_eq!("aaaaa |>\n    bbbbb\n    - c", out);
+    }
+
     #[test]
     fn append_non_wrapping() {
         let mut out = String::new();
@@ -1,4 +1,8 @@
 use crate::poet::class_name::StructName;
+use crate::poet::code_writer::CodeWriter;
+use crate::poet::operator::Operator;
+use crate::poet::{escape_if_necessary, escape_segments_if_necessary};
+use core::fmt;
 
 /**
  * Represents the name of a member (such as a function or a property).
@@ -7,13 +11,114 @@ use crate::poet::class_name::StructName;
  * @param enclosingClassName e.g. `Map.Entry.Companion`, if the member is declared inside the
  * companion object of the Map.Entry class
  * @param simpleName e.g. `isBlank`, `size`
+ * @param operator e.g. `Operator::PLUS`, if the member is an operator function. `%M` then emits
+ * the operator token, so `"%N %M %N"` renders as `a + b`
+ * @param isExtension whether the member is an extension, which can only be called through an
+ * import
  */
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct MemberName {
     pub package_name: String,
     pub enclosing_class_name: Option<StructName>,
     pub simple_name: String,
-    pub operator: Option<String>,
+    pub operator: Option<Operator>,
+    pub is_extension: bool,
 }
 
-impl MemberName {}
+impl MemberName {
+    pub fn new(package_name: impl Into<String>, simple_name: impl Into<String>) -> Self {
+        MemberName {
+            package_name: package_name.into(),
+            enclosing_class_name: None,
+            simple_name: simple_name.into(),
+            operator: None,
+            is_extension: false,
+        }
+    }
+
+    pub fn nested(enclosing_class_name: StructName, simple_name: impl Into<String>) -> Self {
+        MemberName {
+            package_name: enclosing_class_name.package_name.clone(),
+            enclosing_class_name: Some(enclosing_class_name),
+            simple_name: simple_name.into(),
+            operator: None,
+            is_extension: false,
+        }
+    }
+
+    pub fn extension(package_name: impl Into<String>, simple_name: impl Into<String>) -> Self {
+        MemberName {
+            is_extension: true,
+            ..MemberName::new(package_name, simple_name)
+        }
+    }
+
+    ///
+    /// Returns an extension operator function declared at the top level of `package_name`, named
+    /// after the operator's function name, e.g. `plus` for `Operator::PLUS`.
+    ///
+    pub fn with_operator(package_name: impl Into<String>, operator: Operator) -> Self {
+        MemberName {
+            operator: Some(operator.clone()),
+            ..MemberName::extension(package_name, operator.value().function_name)
+        }
+    }
+
+    /// Fully qualified name using `.` as a separator, like `kotlin.String.isBlank`.
+    pub fn canonical_name(&self) -> String {
+        let simple_name = escape_if_necessary(&self.simple_name);
+        match &self.enclosing_class_name {
+            Some(enclosing) => format!("{}.{}", enclosing.canonical_name(), simple_name),
+            None if self.package_name.is_empty() => simple_name,
+            None => format!(
+                "{}.{}",
+                escape_segments_if_necessary(&self.package_name),
+                simple_name
+            ),
+        }
+    }
+
+    pub fn emit(&self, writer: &mut CodeWriter) {
+        match &self.operator {
+            Some(operator) => {
+                // Member operators resolve through their receiver, only top-level ones need an import.
+                if self.enclosing_class_name.is_none() {
+                    writer.lookup_member_name(self);
+                }
+                writer.emit(String::from(operator.value().operator));
+            }
+            None => {
+                let name = writer.lookup_member_name(self);
+                writer.emit(name);
+            }
+        }
+    }
+}
+
+impl fmt::Display for MemberName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.canonical_name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::poet::class_name::StructName;
+    use crate::poet::member_name::MemberName;
+
+    #[test]
+    fn canonical_name() {
+        assert_eq!(
+            "com.phodal.tacos.createTaco",
+            MemberName::new("com.phodal.tacos", "createTaco").canonical_name()
+        );
+        assert_eq!(
+            "com.phodal.Taco.Companion.createTaco",
+            MemberName::nested(
+                StructName::new("com.phodal", "Taco.Companion"),
+                "createTaco"
+            )
+            .canonical_name()
+        );
+    }
+}
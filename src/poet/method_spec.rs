@@ -1,19 +1,731 @@
 use crate::poet::annotation_spec::AnnotationSpec;
-use crate::poet::code_block::CodeBlock;
-use crate::poet::modifier::Modifier;
-use crate::poet::parameter_spec::ParameterSpec;
+use crate::poet::code_block::{Arg, CodeBlock, CodeBlockBuilder};
+use crate::poet::code_writer::CodeWriter;
+use crate::poet::member_name::MemberName;
+use crate::poet::modifier::{check_modifiers, Modifier, Target};
+use crate::poet::name_allocator::NameAllocator;
+use crate::poet::operator::Operator;
+use crate::poet::parameter_spec::{emit_parameters, ParameterSpec};
 use crate::poet::type_name::TypeName;
+use crate::poet::{check_name, DEFAULT_INDENT};
+use core::fmt;
+
+pub const CONSTRUCTOR: &str = "constructor";
+pub const GETTER: &str = "get";
+pub const SETTER: &str = "set";
 
 /** A generated constructor or method declaration. */
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct MethodSpec {
     pub name: String,
     pub modifiers: Vec<Modifier>,
-    pub receiver_type: TypeName,
-    pub return_type: TypeName,
+    pub receiver_type: Option<TypeName>,
+    pub return_type: Option<TypeName>,
     pub parameters: Vec<ParameterSpec>,
     pub annotations: Vec<AnnotationSpec>,
+    pub delegate_constructor: Option<String>,
+    pub delegate_constructor_arguments: Vec<CodeBlock>,
+    pub operator: Option<Operator>,
     pub code: CodeBlock,
+    pub kdoc: CodeBlock,
+    pub return_kdoc: CodeBlock,
+}
+
+impl MethodSpec {
+    pub fn new(builder: &MethodSpecBuilder) -> Self {
+        MethodSpec {
+            name: builder.name.clone(),
+            modifiers: builder.modifiers.clone(),
+            receiver_type: builder.receiver_type.clone(),
+            return_type: builder.return_type.clone(),
+            parameters: builder.parameters.clone(),
+            annotations: builder.annotations.clone(),
+            delegate_constructor: builder.delegate_constructor.clone(),
+            delegate_constructor_arguments: builder.delegate_constructor_arguments.clone(),
+            operator: builder.operator.clone(),
+            code: builder.code.build(),
+            kdoc: builder.kdoc.build(),
+            return_kdoc: builder.return_kdoc.build(),
+        }
+    }
+
+    pub fn builder(name: impl Into<String>) -> MethodSpecBuilder {
+        MethodSpecBuilder::new(name)
+    }
+
+    pub fn constructor_builder() -> MethodSpecBuilder {
+        MethodSpecBuilder::new(CONSTRUCTOR)
+    }
+
+    pub fn getter_builder() -> MethodSpecBuilder {
+        MethodSpecBuilder::new(GETTER)
+    }
+
+    pub fn setter_builder() -> MethodSpecBuilder {
+        MethodSpecBuilder::new(SETTER)
+    }
+
+    ///
+    /// Returns a builder for an `operator` function overloading `operator`, e.g. `plus` for
+    /// `Operator::PLUS`. The parameter count is checked against the operator when built.
+    ///
+    pub fn operator_builder(operator: Operator) -> MethodSpecBuilder {
+        let mut builder = MethodSpecBuilder::new(operator.value().function_name);
+        builder.add_modifiers(vec![Modifier::OPERATOR]);
+        builder.operator = Some(operator);
+        builder
+    }
+
+    pub fn is_constructor(&self) -> bool {
+        self.name == CONSTRUCTOR
+    }
+
+    pub fn is_accessor(&self) -> bool {
+        self.name == GETTER || self.name == SETTER
+    }
+
+    ///
+    /// Returns a [MemberName] for this function declared at the top level of `package_name`, to be
+    /// referenced with `%M`. Functions with a receiver produce extension members.
+    ///
+    pub fn member_name(&self, package_name: impl Into<String>) -> MemberName {
+        let mut member_name = if self.receiver_type.is_some() {
+            MemberName::extension(package_name, &self.name)
+        } else {
+            MemberName::new(package_name, &self.name)
+        };
+        member_name.operator = self.operator.clone();
+        member_name
+    }
+
+    ///
+    /// Returns the doc comment of this function followed by `@param` tags for documented
+    /// parameters and a `@return` tag when the return value is documented.
+    ///
+    pub fn kdoc_with_tags(&self) -> CodeBlock {
+        let mut builder = CodeBlockBuilder::new();
+        builder.add_code(&self.kdoc.ensure_ends_with_newline());
+
+        let documented: Vec<&ParameterSpec> = self
+            .parameters
+            .iter()
+            .filter(|parameter| !parameter.kdoc.is_empty())
+            .collect();
+        let has_tags = !documented.is_empty() || !self.return_kdoc.is_empty();
+        if !self.kdoc.is_empty() && has_tags {
+            builder.add("\n", Vec::<Arg>::new());
+        }
+        for parameter in documented {
+            builder.add("@param %L ", vec![parameter.name.clone()]);
+            builder.add_code(&parameter.kdoc.ensure_ends_with_newline());
+        }
+        if !self.return_kdoc.is_empty() {
+            builder.add("@return ", Vec::<Arg>::new());
+            builder.add_code(&self.return_kdoc.ensure_ends_with_newline());
+        }
+        builder.build()
+    }
+
+    pub fn emit(&self, writer: &mut CodeWriter) {
+        writer.emit_kdoc(&self.kdoc_with_tags());
+        writer.emit_annotations(&self.annotations, false);
+        writer.emit_modifiers(&self.modifiers);
+        if self.is_constructor() {
+            writer.emit(String::from(CONSTRUCTOR));
+        } else if self.is_accessor() {
+            writer.emit(self.name.clone());
+            // An accessor without a body only changes modifiers, like `private set`.
+            if self.code.is_empty() && self.parameters.is_empty() {
+                writer.emit(String::from("\n"));
+                return;
+            }
+        } else {
+            writer.emit(String::from("fun "));
+            if let Some(receiver_type) = &self.receiver_type {
+                receiver_type.emit(writer);
+                writer.emit(String::from("."));
+            }
+            writer.emit_code("%N", vec![self.name.clone()]);
+        }
+
+        let is_setter = self.name == SETTER;
+        emit_parameters(
+            writer,
+            &self.parameters,
+            |writer, parameter| {
+                if is_setter {
+                    // The setter's parameter type is implied by the property.
+                    parameter.emit_annotations_and_modifiers(writer);
+                    writer.emit_code("%N", vec![parameter.name.clone()]);
+                } else {
+                    parameter.emit(writer)
+                }
+            },
+            |writer| self.emit_signature_end(writer),
+        );
+        self.emit_signature_end(writer);
+
+        if !self.has_body() {
+            return;
+        }
+
+        writer.indent(1);
+        writer.emit_block(&self.code);
+        writer.unindent(1);
+        writer.emit(String::from("}\n"));
+    }
+
+    /// Constructors without code end at their signature.
+    fn has_body(&self) -> bool {
+        !self.is_constructor() || !self.code.is_empty()
+    }
+
+    ///
+    /// Emits what follows the parameter list: the return type, the delegate constructor call and
+    /// the opening brace, or the line break ending a constructor without a body.
+    ///
+    fn emit_signature_end(&self, writer: &mut CodeWriter) {
+        if let Some(return_type) = &self.return_type {
+            writer.emit(String::from(":·"));
+            return_type.emit(writer);
+        }
+
+        if let Some(delegate_constructor) = &self.delegate_constructor {
+            let arguments = &self.delegate_constructor_arguments;
+            writer.emit_code(" :·%L(", vec![delegate_constructor.clone()]);
+            writer.emit_group(
+                arguments.len(),
+                |writer, index| writer.emit_block(&arguments[index]),
+                |writer| {
+                    writer.emit(String::from(")"));
+                    self.emit_body_start(writer);
+                },
+            );
+            writer.emit(String::from(")"));
+        }
+
+        self.emit_body_start(writer);
+    }
+
+    fn emit_body_start(&self, writer: &mut CodeWriter) {
+        if self.has_body() {
+            writer.emit_open_brace();
+        } else {
+            writer.emit(String::from("\n"));
+        }
+    }
 }
 
-impl MethodSpec {}
+impl fmt::Display for MethodSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut out = String::new();
+        let mut writer = CodeWriter::new(&mut out, DEFAULT_INDENT);
+        self.emit(&mut writer);
+        writer.close();
+        write!(f, "{}", out)
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct MethodSpecBuilder {
+    pub name: String,
+    pub modifiers: Vec<Modifier>,
+    pub receiver_type: Option<TypeName>,
+    pub return_type: Option<TypeName>,
+    pub parameters: Vec<ParameterSpec>,
+    pub annotations: Vec<AnnotationSpec>,
+    pub delegate_constructor: Option<String>,
+    pub delegate_constructor_arguments: Vec<CodeBlock>,
+    pub operator: Option<Operator>,
+    pub code: CodeBlockBuilder,
+    pub kdoc: CodeBlockBuilder,
+    pub return_kdoc: CodeBlockBuilder,
+    /// Names in scope in the body: parameters, locals and anything from the enclosing scope.
+    pub name_allocator: NameAllocator,
+}
+
+impl MethodSpecBuilder {
+    pub fn new(name: impl Into<String>) -> Self {
+        MethodSpecBuilder {
+            name: name.into(),
+            modifiers: vec![],
+            receiver_type: None,
+            return_type: None,
+            parameters: vec![],
+            annotations: vec![],
+            delegate_constructor: None,
+            delegate_constructor_arguments: vec![],
+            operator: None,
+            code: CodeBlockBuilder::new(),
+            kdoc: CodeBlockBuilder::new(),
+            return_kdoc: CodeBlockBuilder::new(),
+            name_allocator: NameAllocator::new(),
+        }
+    }
+
+    pub fn add_modifiers(&mut self, modifiers: Vec<Modifier>) -> &mut MethodSpecBuilder {
+        self.modifiers.extend(modifiers);
+        self
+    }
+
+    pub fn add_kdoc<T: Into<Arg>>(&mut self, format: &str, args: Vec<T>) -> &mut MethodSpecBuilder {
+        self.kdoc.add(format, args);
+        self
+    }
+
+    /// Documents the return value, emitted as the `@return` tag.
+    pub fn add_return_kdoc<T: Into<Arg>>(
+        &mut self,
+        format: &str,
+        args: Vec<T>,
+    ) -> &mut MethodSpecBuilder {
+        self.return_kdoc.add(format, args);
+        self
+    }
+
+    pub fn add_annotation(&mut self, annotation: AnnotationSpec) -> &mut MethodSpecBuilder {
+        self.annotations.push(annotation);
+        self
+    }
+
+    pub fn returns(&mut self, return_type: TypeName) -> &mut MethodSpecBuilder {
+        if self.name == CONSTRUCTOR {
+            panic!("{} cannot have a return type", self.name);
+        }
+        self.return_type = Some(return_type);
+        self
+    }
+
+    ///
+    /// Makes this an extension function on `receiver_type`, emitted as `fun Receiver.name()`.
+    ///
+    pub fn receiver(&mut self, receiver_type: TypeName) -> &mut MethodSpecBuilder {
+        if self.name == CONSTRUCTOR {
+            panic!("{} cannot have receiver type", self.name);
+        }
+        self.receiver_type = Some(receiver_type);
+        self
+    }
+
+    pub fn add_parameter(&mut self, parameter: ParameterSpec) -> &mut MethodSpecBuilder {
+        let name = parameter.name.as_str();
+        if !self.name_allocator.reserve(name) {
+            panic!(
+                "parameter {} of {} clashes with another name in scope",
+                name, self.name
+            );
+        }
+        self.parameters.push(parameter);
+        self
+    }
+
+    ///
+    /// Returns a fresh local variable name based on `suggestion` that doesn't shadow a parameter,
+    /// an earlier local, or a name from the enclosing scope.
+    ///
+    pub fn new_local(&mut self, suggestion: impl AsRef<str>) -> String {
+        self.name_allocator.allocate(suggestion)
+    }
+
+    ///
+    /// Nests this method in `scope`, typically `TypeSpecBuilder::name_allocator()`, so that locals
+    /// don't shadow the enclosing properties. Call it before allocating locals.
+    ///
+    pub fn enclosing_scope(&mut self, scope: &NameAllocator) -> &mut MethodSpecBuilder {
+        let mut name_allocator = scope.clone();
+        name_allocator.reserve_all(&self.name_allocator);
+        self.name_allocator = name_allocator;
+        self
+    }
+
+    pub fn call_this_constructor(&mut self, args: Vec<CodeBlock>) -> &mut MethodSpecBuilder {
+        self.call_constructor("this", args)
+    }
+
+    pub fn call_super_constructor(&mut self, args: Vec<CodeBlock>) -> &mut MethodSpecBuilder {
+        self.call_constructor("super", args)
+    }
+
+    fn call_constructor(
+        &mut self,
+        constructor: &str,
+        args: Vec<CodeBlock>,
+    ) -> &mut MethodSpecBuilder {
+        if self.name != CONSTRUCTOR {
+            panic!("only constructors can delegate to other constructors!");
+        }
+        self.delegate_constructor = Some(String::from(constructor));
+        self.delegate_constructor_arguments = args;
+        self
+    }
+
+    pub fn add_code<T: Into<Arg>>(&mut self, format: &str, args: Vec<T>) -> &mut MethodSpecBuilder {
+        self.code.add(format, args);
+        self
+    }
+
+    pub fn add_statement<T: Into<Arg>>(
+        &mut self,
+        format: &str,
+        args: Vec<T>,
+    ) -> &mut MethodSpecBuilder {
+        self.code.add_statement(format, args);
+        self
+    }
+
+    pub fn add_comment<T: Into<Arg>>(
+        &mut self,
+        format: &str,
+        args: Vec<T>,
+    ) -> &mut MethodSpecBuilder {
+        self.code.add_comment(format, args);
+        self
+    }
+
+    pub fn build(&self) -> MethodSpec {
+        check_name(&self.name);
+        let declaration = format!("function {}", self.name);
+        check_modifiers(&self.modifiers, Target::FUNCTION, &declaration);
+
+        let varargs = self.parameters.iter().filter(|p| p.is_vararg()).count();
+        if varargs > 1 {
+            panic!(
+                "{} has {} vararg parameters, only one is allowed",
+                self.name, varargs
+            );
+        }
+        if let Some(operator) = &self.operator {
+            let expected = operator.parameter_count();
+            if self.parameters.len() != expected {
+                panic!(
+                    "operator {} must have exactly {} parameter(s) but has {}",
+                    self.name,
+                    expected,
+                    self.parameters.len()
+                );
+            }
+            if operator.is_assign() && self.return_type.as_ref().is_some_and(|t| !t.is_unit()) {
+                panic!("assignment operator {} must return Unit", self.name);
+            }
+        }
+        MethodSpec::new(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::poet::annotation_spec::AnnotationSpec;
+    use crate::poet::class_name::StructName;
+    use crate::poet::code_block::CodeBlock;
+    use crate::poet::method_spec::MethodSpec;
+    use crate::poet::modifier::Modifier;
+    use crate::poet::operator::Operator;
+    use crate::poet::parameter_spec::ParameterSpec;
+    use crate::poet::property_spec::PropertySpec;
+    use crate::poet::type_name::TypeName;
+    use crate::poet::type_spec::TypeSpec;
+
+    #[test]
+    #[should_panic(expected = "can't escape identifier \"eat.taco\": it contains '.'")]
+    fn invalid_name() {
+        MethodSpec::builder("eat.taco").build();
+    }
+
+    #[test]
+    fn function() {
+        let method = MethodSpec::builder("taco")
+            .add_parameter(ParameterSpec::builder("count", TypeName::get("", "Int")).build())
+            .returns(TypeName::get("", "String"))
+            .add_statement("return %S", vec![String::from("taco")])
+            .build();
+
+        assert_eq!(
+            "fun taco(count: Int): String {\n    return \"taco\"\n}\n",
+            format!("{}", method)
+        );
+    }
+
+    #[test]
+    fn long_parameter_list_wraps_one_per_line() {
+        let mut builder = MethodSpec::builder("eat");
+        for name in ["taco", "burrito", "quesadilla", "enchilada", "tamale"] {
+            builder.add_parameter(
+                ParameterSpec::builder(name, TypeName::get("", "Ingredients")).build(),
+            );
+        }
+        let method = builder.build();
+
+        assert_eq!(
+            "fun eat(
+    taco: Ingredients,
+    burrito: Ingredients,
+    quesadilla: Ingredients,
+    enchilada: Ingredients,
+    tamale: Ingredients,
+) {
+}
+",
+            format!("{}", method)
+        );
+    }
+
+    #[test]
+    fn long_return_type_wraps_the_parameter_list() {
+        let method = MethodSpec::builder("eat")
+            .add_parameter(ParameterSpec::builder("taco", TypeName::get("", "Ingredients")).build())
+            .add_parameter(
+                ParameterSpec::builder("burrito", TypeName::get("", "Ingredients")).build(),
+            )
+            .returns(TypeName::get(
+                "",
+                "AVeryLongReturnTypeNameThatFillsTheRestOfTheLineEntirely",
+            ))
+            .build();
+
+        assert_eq!(
+            "fun eat(
+    taco: Ingredients,
+    burrito: Ingredients,
+): AVeryLongReturnTypeNameThatFillsTheRestOfTheLineEntirely {
+}
+",
+            format!("{}", method)
+        );
+    }
+
+    #[test]
+    fn annotations_are_emitted_one_per_line() {
+        let method = MethodSpec::builder("taco")
+            .add_annotation(AnnotationSpec::builder(StructName::new("", "Test")).build())
+            .add_annotation(
+                AnnotationSpec::builder(StructName::new("", "Suppress"))
+                    .add_member("%S", vec!["unused"])
+                    .build(),
+            )
+            .build();
+
+        assert_eq!(
+            "@Test\n@Suppress(\"unused\")\nfun taco() {\n}\n",
+            format!("{}", method)
+        );
+    }
+
+    #[test]
+    fn constructor_delegates_to_this() {
+        let constructor = MethodSpec::constructor_builder()
+            .add_modifiers(vec![Modifier::PRIVATE])
+            .add_parameter(ParameterSpec::builder("a", TypeName::get("", "Int")).build())
+            .call_this_constructor(vec![
+                CodeBlock::of("%N", vec![String::from("a")]),
+                CodeBlock::of("%L", vec![String::from("0")]),
+            ])
+            .build();
+
+        assert!(constructor.is_constructor());
+        assert_eq!(
+            "private constructor(a: Int) : this(a, 0)\n",
+            format!("{}", constructor)
+        );
+    }
+
+    #[test]
+    fn constructor_with_body_delegates_to_super() {
+        let constructor = MethodSpec::constructor_builder()
+            .call_super_constructor(vec![])
+            .add_statement("println(%S)", vec!["taco"])
+            .build();
+
+        assert_eq!(
+            "constructor() : super() {\n    println(\"taco\")\n}\n",
+            format!("{}", constructor)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "only constructors can delegate to other constructors!")]
+    fn only_constructors_delegate() {
+        MethodSpec::builder("taco").call_this_constructor(vec![]);
+    }
+
+    #[test]
+    fn operator_function() {
+        let taco = TypeName::get("", "Taco");
+        let plus = MethodSpec::operator_builder(Operator::PLUS)
+            .receiver(taco.clone())
+            .add_parameter(ParameterSpec::builder("other", taco.clone()).build())
+            .returns(taco)
+            .add_statement("return %N", vec!["other"])
+            .build();
+
+        assert_eq!(
+            "operator fun Taco.plus(other: Taco): Taco {\n    return other\n}\n",
+            format!("{}", plus)
+        );
+        assert_eq!(
+            Some(Operator::PLUS),
+            plus.member_name("com.phodal").operator
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "operator unaryMinus must have exactly 0 parameter(s) but has 1")]
+    fn unary_operator_takes_no_parameters() {
+        MethodSpec::operator_builder(Operator::UNARY_MINUS)
+            .add_parameter(ParameterSpec::builder("other", TypeName::get("", "Int")).build())
+            .build();
+    }
+
+    #[test]
+    #[should_panic(expected = "operator times must have exactly 1 parameter(s) but has 0")]
+    fn binary_operator_takes_one_parameter() {
+        MethodSpec::operator_builder(Operator::TIMES).build();
+    }
+
+    #[test]
+    #[should_panic(expected = "assignment operator plusAssign must return Unit")]
+    fn assign_operator_returns_unit() {
+        MethodSpec::operator_builder(Operator::PLUS_ASSIGN)
+            .add_parameter(ParameterSpec::builder("other", TypeName::get("", "Int")).build())
+            .returns(TypeName::get("", "Int"))
+            .build();
+    }
+
+    #[test]
+    fn assign_operator_may_declare_unit() {
+        let plus_assign = MethodSpec::operator_builder(Operator::PLUS_ASSIGN)
+            .add_parameter(ParameterSpec::builder("other", TypeName::get("", "Int")).build())
+            .returns(TypeName::get("", "Unit"))
+            .build();
+        assert_eq!(
+            "operator fun plusAssign(other: Int): Unit {\n}\n",
+            format!("{}", plus_assign)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "taco has 2 vararg parameters, only one is allowed")]
+    fn only_one_vararg() {
+        let vararg = |name: &str| {
+            ParameterSpec::builder(name, TypeName::get("", "String"))
+                .add_modifiers(vec![Modifier::VARARG])
+                .build()
+        };
+        MethodSpec::builder("taco")
+            .add_parameter(vararg("fillings"))
+            .add_parameter(vararg("toppings"))
+            .build();
+    }
+
+    #[test]
+    fn locals_do_not_shadow_parameters() {
+        let mut builder = MethodSpec::builder("count");
+        builder
+            .add_parameter(ParameterSpec::builder("result", TypeName::get("", "Int")).build())
+            .returns(TypeName::get("", "Int"));
+        let local = builder.new_local("result");
+        let method = builder
+            .add_statement(
+                "val %N = %N + 1",
+                vec![local.clone(), String::from("result")],
+            )
+            .add_statement("return %N", vec![local])
+            .build();
+
+        assert_eq!(
+            "fun count(result: Int): Int {
+    val result_ = result + 1
+    return result_
+}
+",
+            format!("{}", method)
+        );
+    }
+
+    #[test]
+    fn locals_do_not_shadow_enclosing_properties() {
+        let mut taco = TypeSpec::class_builder("Taco");
+        taco.add_property(
+            PropertySpec::builder("filling", TypeName::get("", "String"))
+                .initializer("%S", vec!["beef"])
+                .build(),
+        );
+        let mut builder = MethodSpec::builder("eat");
+        builder.enclosing_scope(&taco.name_allocator());
+        assert_eq!("filling_", builder.new_local("filling"));
+        assert_eq!("bite", builder.new_local("bite"));
+    }
+
+    #[test]
+    #[should_panic(expected = "parameter a of taco clashes with another name in scope")]
+    fn duplicate_parameter_names() {
+        MethodSpec::builder("taco")
+            .add_parameter(ParameterSpec::builder("a", TypeName::get("", "Int")).build())
+            .add_parameter(ParameterSpec::builder("a", TypeName::get("", "Int")).build());
+    }
+
+    #[test]
+    #[should_panic(expected = "parameter when of taco clashes with another name in scope")]
+    fn duplicate_keyword_parameter_names() {
+        MethodSpec::builder("taco")
+            .add_parameter(ParameterSpec::builder("when", TypeName::get("", "Int")).build())
+            .add_parameter(ParameterSpec::builder("when", TypeName::get("", "Int")).build());
+    }
+
+    #[test]
+    fn keyword_parameter_is_not_shadowed() {
+        let mut builder = MethodSpec::builder("taco");
+        builder.add_parameter(ParameterSpec::builder("when", TypeName::get("", "Int")).build());
+        assert_eq!("when_", builder.new_local("when"));
+    }
+
+    #[test]
+    fn non_ascii_parameter_is_not_shadowed() {
+        let mut builder = MethodSpec::builder("taco");
+        builder.add_parameter(ParameterSpec::builder("größe", TypeName::get("", "Int")).build());
+        assert_eq!("größe_", builder.new_local("größe"));
+    }
+
+    #[test]
+    fn non_ascii_enclosing_property_is_not_shadowed() {
+        let mut taco = TypeSpec::class_builder("Taco");
+        taco.add_property(PropertySpec::builder("名前", TypeName::get("", "String")).build());
+        let mut builder = MethodSpec::builder("eat");
+        builder.enclosing_scope(&taco.name_allocator());
+        assert_eq!("名前_", builder.new_local("名前"));
+    }
+
+    #[test]
+    fn kdoc_with_param_and_return_tags() {
+        let method = MethodSpec::builder("eat")
+            .add_kdoc(
+                "Eats the taco.\n\nNever returns */ early.",
+                Vec::<String>::new(),
+            )
+            .add_parameter(
+                ParameterSpec::builder("bites", TypeName::get("", "Int"))
+                    .add_kdoc("how many bites to take", Vec::<String>::new())
+                    .build(),
+            )
+            .add_parameter(ParameterSpec::builder("fast", TypeName::get("", "Boolean")).build())
+            .returns(TypeName::get("", "Int"))
+            .add_return_kdoc("the bites left", Vec::<String>::new())
+            .add_statement("return %L", vec!["0"])
+            .build();
+
+        assert_eq!(
+            "/**
+ * Eats the taco.
+ *
+ * Never returns *&#47; early.
+ *
+ * @param bites how many bites to take
+ * @return the bites left
+ */
+fun eat(bites: Int, fast: Boolean): Int {
+    return 0
+}
+",
+            format!("{}", method)
+        );
+    }
+}
@@ -1,10 +1,47 @@
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
 pub const DEFAULT_INDENT: &str = "    ";
 
+/// Reserved words of the Charj language. They can't be used as identifiers without escaping.
+pub const KEYWORDS: [&str; 30] = [
+    "as",
+    "break",
+    "class",
+    "continue",
+    "do",
+    "else",
+    "false",
+    "for",
+    "fun",
+    "if",
+    "in",
+    "interface",
+    "is",
+    "null",
+    "object",
+    "package",
+    "return",
+    "struct",
+    "super",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "type",
+    "typealias",
+    "typeof",
+    "val",
+    "var",
+    "when",
+    "while",
+];
+
 pub mod annotation_spec;
 pub mod class_name;
 pub mod code_block;
 pub mod code_writer;
 pub mod file_spec;
+pub mod format_options;
 pub mod import;
 pub mod line_wrapper;
 pub mod member_name;
@@ -14,35 +51,97 @@ pub mod name_allocator;
 pub mod operator;
 pub mod parameter_spec;
 pub mod parameterized_type_name;
+pub mod project_spec;
 pub mod property_spec;
+pub mod sink;
 pub mod type_alias_spec;
 pub mod type_name;
 pub mod type_spec;
 pub mod type_variable_name;
 pub mod wildcard_type_name;
 
-pub fn index_of(chars: &Vec<char>, p: usize) -> i32 {
-    let mut index: i32 = -1;
-    for i in p + 1..chars.len() {
-        if chars[i] == '%' {
-            index = i as i32;
+/// Characters that can't appear in an identifier, even inside backticks.
+const ILLEGAL_CHARACTERS_TO_ESCAPE: [char; 11] =
+    ['.', ';', '[', ']', '/', '<', '>', ':', '\\', '`', '\n'];
+
+///
+/// Returns true if `name` can be written as is: ASCII letters, digits and underscores, not
+/// starting with a digit, and not a keyword.
+///
+pub fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) if first.is_ascii_alphabetic() || first == '_' => {}
+        _ => return false,
+    }
+    chars.all(|c| c.is_ascii_alphanumeric() || c == '_') && !KEYWORDS.contains(&name)
+}
+
+///
+/// Wraps `name` in backticks when it isn't a plain identifier, e.g. `` `in` `` or `` `naïve` ``.
+/// Panics for names that can't be escaped at all.
+///
+pub fn escape_if_necessary(name: &str) -> String {
+    if is_identifier(name) {
+        return String::from(name);
+    }
+    if name.is_empty() {
+        panic!("identifier must not be empty");
+    }
+    if let Some(c) = name
+        .chars()
+        .find(|c| ILLEGAL_CHARACTERS_TO_ESCAPE.contains(c))
+    {
+        panic!("can't escape identifier {:?}: it contains {:?}", name, c);
+    }
+    format!("`{}`", name)
+}
+
+/// Panics if `name` can't be emitted even in backticks, so builders reject it up front.
+pub fn check_name(name: &str) {
+    escape_if_necessary(name);
+}
+
+/// Escapes each `.`-separated segment of a qualified name like a package.
+pub fn escape_segments_if_necessary(name: &str) -> String {
+    if name.is_empty() {
+        return String::new();
+    }
+    let segments: Vec<String> = name.split('.').map(escape_if_necessary).collect();
+    segments.join(".")
+}
+
+///
+/// Returns the number of columns `c` occupies in a terminal or editor: 0 for control characters
+/// and combining marks, 2 for wide characters like CJK ideographs, and 1 otherwise.
+///
+pub fn char_width(c: char) -> usize {
+    UnicodeWidthChar::width(c).unwrap_or(0)
+}
+
+/// Returns the number of columns `s` occupies when displayed. See [char_width].
+pub fn display_width(s: &str) -> usize {
+    UnicodeWidthStr::width(s)
+}
+
+pub fn index_of(chars: &[char], start_index: usize) -> i32 {
+    for (index, ch) in chars.iter().enumerate().skip(start_index) {
+        if *ch == '%' {
+            return index as i32;
         }
     }
 
-    index
+    -1
 }
 
-pub fn index_of_any(chars: &[char], special_chars: [char; 3], start_index: usize) -> i32 {
-    for index in start_index..chars.len() {
-        let ch: char = chars[index];
-        for schar in special_chars.iter() {
-            if *schar == ch {
-                return index as i32;
-            }
+pub fn index_of_any(chars: &[char], special_chars: &[char], start_index: usize) -> i32 {
+    for (index, ch) in chars.iter().enumerate().skip(start_index) {
+        if special_chars.contains(ch) {
+            return index as i32;
         }
     }
 
-    return -1;
+    -1
 }
 
 pub fn string_literal_with_quotes(
@@ -65,13 +164,11 @@ pub fn string_literal_with_quotes(
 
     // if !is_constant_context && value.contains("\n") {
     let mut result = String::new();
-    result.push_str("\"");
+    result.push('"');
 
-    let chars: Vec<char> = value.chars().collect();
-    let mut i = 0;
-    for c in chars {
+    for (i, c) in value.chars().enumerate() {
         if c == '\'' {
-            result.push_str("'");
+            result.push('\'');
             continue;
         }
         if c == '\"' {
@@ -79,19 +176,18 @@ pub fn string_literal_with_quotes(
             continue;
         }
 
-        result.push_str(&*character_literal_without_single_quotes(c));
+        result.push_str(&character_literal_without_single_quotes(c));
         if c == '\n' && i + 1 < value.len() {
             result.push_str("\"\n");
-            result.push_str(&*indent);
-            result.push_str(&*indent);
+            result.push_str(indent);
+            result.push_str(indent);
             result.push_str("+ \"");
         }
-        i = i + 1;
     }
 
-    result.push_str("\"");
+    result.push('"');
     // }
-    return result;
+    result
 }
 
 pub fn character_literal_without_single_quotes(c: char) -> String {
@@ -106,9 +202,9 @@ pub fn character_literal_without_single_quotes(c: char) -> String {
         '\\' => String::from("\\\\"),  /* \u005c: backslash (\) */
         _ => {
             if c.is_ascii() {
-                return c.to_string();
+                c.to_string()
             } else {
-                return c.escape_unicode().to_string();
+                c.escape_unicode().to_string()
             }
         }
     }
@@ -116,7 +212,41 @@ pub fn character_literal_without_single_quotes(c: char) -> String {
 
 #[cfg(test)]
 mod tests {
-    use crate::poet::{character_literal_without_single_quotes, string_literal_with_quotes};
+    use crate::poet::{
+        character_literal_without_single_quotes, display_width, escape_if_necessary,
+        escape_segments_if_necessary, string_literal_with_quotes,
+    };
+
+    #[test]
+    fn display_widths() {
+        assert_eq!(5, display_width("taco!"));
+        assert_eq!(4, display_width("塔可"));
+        assert_eq!(4, display_width("타코"));
+        assert_eq!(6, display_width("ｔａｃ"));
+        assert_eq!(1, display_width("e\u{301}"));
+        assert_eq!(2, display_width("🌮"));
+    }
+
+    #[test]
+    fn escapes_keywords_and_non_ascii_names() {
+        assert_eq!("taco", escape_if_necessary("taco"));
+        assert_eq!("_1", escape_if_necessary("_1"));
+        assert_eq!("`in`", escape_if_necessary("in"));
+        assert_eq!("`type`", escape_if_necessary("type"));
+        assert_eq!("`naïve`", escape_if_necessary("naïve"));
+        assert_eq!("`1st`", escape_if_necessary("1st"));
+        assert_eq!("`taco shell`", escape_if_necessary("taco shell"));
+        assert_eq!(
+            "com.`in`.tacos",
+            escape_segments_if_necessary("com.in.tacos")
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "can't escape identifier \"a`b\": it contains '`'")]
+    fn rejects_unescapable_names() {
+        escape_if_necessary("a`b");
+    }
 
     #[test]
     #[rustfmt::skip]
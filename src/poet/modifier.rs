@@ -1,6 +1,6 @@
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct BaseModifier {
-    pub keyword: &'static str,
+    pub keyword: String,
     // todo: check different behavior for hashset & vector
     pub targets: Vec<Target>,
 }
@@ -15,6 +15,7 @@ pub enum Target {
     FUNCTION,
     PROPERTY,
     INTERFACE,
+    TYPE_ALIAS,
 }
 
 #[derive(Clone, Debug)]
@@ -25,45 +26,366 @@ pub struct PublicModifier {
 impl Default for PublicModifier {
     fn default() -> Self {
         PublicModifier {
-            modifier: BaseModifier {
-                keyword: "public",
-                targets: [Target::PROPERTY].iter().cloned().collect(),
-            },
+            modifier: Modifier::PUBLIC.value(),
         }
     }
 }
 
+///
+/// Modifiers are declared in their canonical order, the order recommended by the Kotlin coding
+/// conventions. Sorting a list of modifiers puts them in the order they should be emitted in.
+///
 #[allow(non_camel_case_types)]
-#[derive(Serialize, Deserialize, Hash, Eq, PartialEq, Debug, Clone)]
+#[derive(Serialize, Deserialize, Hash, Eq, PartialEq, Ord, PartialOrd, Debug, Clone)]
 pub enum Modifier {
     PUBLIC,
     PROTECTED,
     PRIVATE,
     INTERNAL,
+    EXPECT,
+    ACTUAL,
+    FINAL,
+    OPEN,
+    ABSTRACT,
+    SEALED,
+    CONST,
+    EXTERNAL,
+    OVERRIDE,
+    LATEINIT,
+    TAILREC,
+    VARARG,
+    SUSPEND,
+    INNER,
+    ENUM,
+    ANNOTATION,
+    FUN,
+    COMPANION,
+    INLINE,
+    VALUE,
+    NOINLINE,
+    CROSSINLINE,
+    REIFIED,
+    INFIX,
+    OPERATOR,
+    DATA,
+    IN,
+    OUT,
 }
 
 impl Modifier {
-    fn value(&self) -> BaseModifier {
+    pub fn value(&self) -> BaseModifier {
         match *self {
             Modifier::PUBLIC => BaseModifier {
-                keyword: "public",
+                keyword: String::from("public"),
+                targets: vec![
+                    Target::CLASS,
+                    Target::INTERFACE,
+                    Target::FUNCTION,
+                    Target::PROPERTY,
+                    Target::TYPE_ALIAS,
+                ],
+            },
+            Modifier::PROTECTED => BaseModifier {
+                keyword: String::from("protected"),
+                targets: vec![
+                    Target::CLASS,
+                    Target::INTERFACE,
+                    Target::FUNCTION,
+                    Target::PROPERTY,
+                ],
+            },
+            Modifier::PRIVATE => BaseModifier {
+                keyword: String::from("private"),
+                targets: vec![
+                    Target::CLASS,
+                    Target::INTERFACE,
+                    Target::FUNCTION,
+                    Target::PROPERTY,
+                    Target::TYPE_ALIAS,
+                ],
+            },
+            Modifier::INTERNAL => BaseModifier {
+                keyword: String::from("internal"),
+                targets: vec![
+                    Target::CLASS,
+                    Target::INTERFACE,
+                    Target::FUNCTION,
+                    Target::PROPERTY,
+                    Target::TYPE_ALIAS,
+                ],
+            },
+            Modifier::EXPECT => BaseModifier {
+                keyword: String::from("expect"),
+                targets: vec![
+                    Target::CLASS,
+                    Target::INTERFACE,
+                    Target::FUNCTION,
+                    Target::PROPERTY,
+                ],
+            },
+            Modifier::ACTUAL => BaseModifier {
+                keyword: String::from("actual"),
+                targets: vec![
+                    Target::CLASS,
+                    Target::INTERFACE,
+                    Target::FUNCTION,
+                    Target::PROPERTY,
+                ],
+            },
+            Modifier::FINAL => BaseModifier {
+                keyword: String::from("final"),
+                targets: vec![Target::CLASS, Target::FUNCTION, Target::PROPERTY],
+            },
+            Modifier::OPEN => BaseModifier {
+                keyword: String::from("open"),
+                targets: vec![Target::CLASS, Target::FUNCTION, Target::PROPERTY],
+            },
+            Modifier::ABSTRACT => BaseModifier {
+                keyword: String::from("abstract"),
+                targets: vec![Target::CLASS, Target::FUNCTION, Target::PROPERTY],
+            },
+            Modifier::SEALED => BaseModifier {
+                keyword: String::from("sealed"),
+                targets: vec![Target::CLASS, Target::INTERFACE],
+            },
+            Modifier::CONST => BaseModifier {
+                keyword: String::from("const"),
+                targets: vec![Target::PROPERTY],
+            },
+            Modifier::EXTERNAL => BaseModifier {
+                keyword: String::from("external"),
+                targets: vec![Target::CLASS, Target::FUNCTION, Target::PROPERTY],
+            },
+            Modifier::OVERRIDE => BaseModifier {
+                keyword: String::from("override"),
+                targets: vec![Target::FUNCTION, Target::PROPERTY],
+            },
+            Modifier::LATEINIT => BaseModifier {
+                keyword: String::from("lateinit"),
                 targets: vec![Target::PROPERTY],
             },
-            _ => BaseModifier {
-                keyword: "",
-                targets: Default::default(),
+            Modifier::TAILREC => BaseModifier {
+                keyword: String::from("tailrec"),
+                targets: vec![Target::FUNCTION],
+            },
+            Modifier::VARARG => BaseModifier {
+                keyword: String::from("vararg"),
+                targets: vec![Target::PARAMETER],
+            },
+            Modifier::SUSPEND => BaseModifier {
+                keyword: String::from("suspend"),
+                targets: vec![Target::FUNCTION],
+            },
+            Modifier::INNER => BaseModifier {
+                keyword: String::from("inner"),
+                targets: vec![Target::CLASS],
+            },
+            Modifier::ENUM => BaseModifier {
+                keyword: String::from("enum"),
+                targets: vec![Target::CLASS],
+            },
+            Modifier::ANNOTATION => BaseModifier {
+                keyword: String::from("annotation"),
+                targets: vec![Target::CLASS],
+            },
+            Modifier::FUN => BaseModifier {
+                keyword: String::from("fun"),
+                targets: vec![Target::INTERFACE],
+            },
+            Modifier::COMPANION => BaseModifier {
+                keyword: String::from("companion"),
+                targets: vec![Target::CLASS],
+            },
+            Modifier::INLINE => BaseModifier {
+                keyword: String::from("inline"),
+                targets: vec![Target::FUNCTION, Target::PROPERTY],
+            },
+            Modifier::VALUE => BaseModifier {
+                keyword: String::from("value"),
+                targets: vec![Target::CLASS],
+            },
+            Modifier::NOINLINE => BaseModifier {
+                keyword: String::from("noinline"),
+                targets: vec![Target::PARAMETER],
+            },
+            Modifier::CROSSINLINE => BaseModifier {
+                keyword: String::from("crossinline"),
+                targets: vec![Target::PARAMETER],
+            },
+            Modifier::REIFIED => BaseModifier {
+                keyword: String::from("reified"),
+                targets: vec![Target::TYPE_PARAMETER],
+            },
+            Modifier::INFIX => BaseModifier {
+                keyword: String::from("infix"),
+                targets: vec![Target::FUNCTION],
+            },
+            Modifier::OPERATOR => BaseModifier {
+                keyword: String::from("operator"),
+                targets: vec![Target::FUNCTION],
+            },
+            Modifier::DATA => BaseModifier {
+                keyword: String::from("data"),
+                targets: vec![Target::CLASS],
+            },
+            Modifier::IN => BaseModifier {
+                keyword: String::from("in"),
+                targets: vec![Target::VARIANCE_ANNOTATION],
             },
+            Modifier::OUT => BaseModifier {
+                keyword: String::from("out"),
+                targets: vec![Target::VARIANCE_ANNOTATION],
+            },
+        }
+    }
+
+    pub fn is_allowed_on(&self, target: &Target) -> bool {
+        self.value().targets.contains(target)
+    }
+
+    pub fn is_visibility(&self) -> bool {
+        matches!(
+            self,
+            Modifier::PUBLIC | Modifier::PROTECTED | Modifier::PRIVATE | Modifier::INTERNAL
+        )
+    }
+}
+
+///
+/// Groups of modifiers that can't be combined: a declaration carries at most one modifier from
+/// each group.
+///
+pub const MUTUALLY_EXCLUSIVE: [&[Modifier]; 9] = [
+    &[
+        Modifier::PUBLIC,
+        Modifier::PROTECTED,
+        Modifier::PRIVATE,
+        Modifier::INTERNAL,
+    ],
+    &[
+        Modifier::FINAL,
+        Modifier::OPEN,
+        Modifier::ABSTRACT,
+        Modifier::SEALED,
+    ],
+    &[Modifier::EXPECT, Modifier::ACTUAL],
+    &[Modifier::CONST, Modifier::LATEINIT],
+    &[Modifier::NOINLINE, Modifier::CROSSINLINE],
+    &[Modifier::IN, Modifier::OUT],
+    &[Modifier::DATA, Modifier::ABSTRACT],
+    &[Modifier::DATA, Modifier::OPEN],
+    &[Modifier::DATA, Modifier::INNER],
+];
+
+///
+/// Validates `modifiers` for a declaration of `target`: each modifier must apply to the target
+/// and no two may be mutually exclusive.
+///
+pub fn check_modifiers(modifiers: &[Modifier], target: Target, declaration: &str) {
+    check_target(modifiers, target, declaration);
+    check_conflicts(modifiers, declaration);
+}
+
+pub fn check_conflicts(modifiers: &[Modifier], declaration: &str) {
+    for group in MUTUALLY_EXCLUSIVE.iter() {
+        let present: Vec<&Modifier> = group
+            .iter()
+            .filter(|modifier| modifiers.contains(modifier))
+            .collect();
+        if present.len() > 1 {
+            panic!(
+                "modifiers {:?} and {:?} are mutually exclusive on {}",
+                present[0], present[1], declaration
+            );
+        }
+    }
+}
+
+///
+/// Panics unless every one of `modifiers` may be applied to `target`. `declaration` names the
+/// declaration being built, like `function taco`.
+///
+pub fn check_target(modifiers: &[Modifier], target: Target, declaration: &str) {
+    for modifier in modifiers {
+        if !modifier.is_allowed_on(&target) {
+            panic!(
+                "modifier {:?} is not allowed on {}, it only applies to {:?}",
+                modifier,
+                declaration,
+                modifier.value().targets
+            );
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::poet::modifier::Modifier;
+    use crate::poet::modifier::{check_modifiers, check_target, Modifier, Target};
 
     #[test]
     fn should_enum() {
         let public = Modifier::PUBLIC;
         println!("{:?}", public.value());
     }
+
+    #[test]
+    fn keywords() {
+        assert_eq!("abstract", Modifier::ABSTRACT.value().keyword);
+        assert_eq!("override", Modifier::OVERRIDE.value().keyword);
+        assert_eq!("out", Modifier::OUT.value().keyword);
+    }
+
+    #[test]
+    fn canonical_order() {
+        let mut modifiers = vec![
+            Modifier::DATA,
+            Modifier::OVERRIDE,
+            Modifier::SUSPEND,
+            Modifier::OPEN,
+            Modifier::PRIVATE,
+        ];
+        modifiers.sort();
+        assert_eq!(
+            vec![
+                Modifier::PRIVATE,
+                Modifier::OPEN,
+                Modifier::OVERRIDE,
+                Modifier::SUSPEND,
+                Modifier::DATA,
+            ],
+            modifiers
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "modifier DATA is not allowed on function taco")]
+    fn modifier_must_match_target() {
+        check_target(
+            &[Modifier::OPEN, Modifier::DATA],
+            Target::FUNCTION,
+            "function taco",
+        );
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "modifiers PUBLIC and PRIVATE are mutually exclusive on property taco"
+    )]
+    fn visibilities_are_exclusive() {
+        check_modifiers(
+            &[Modifier::PRIVATE, Modifier::PUBLIC],
+            Target::PROPERTY,
+            "property taco",
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "modifiers OPEN and ABSTRACT are mutually exclusive on class Taco")]
+    fn modalities_are_exclusive() {
+        check_modifiers(
+            &[Modifier::ABSTRACT, Modifier::OPEN],
+            Target::CLASS,
+            "class Taco",
+        );
+    }
 }
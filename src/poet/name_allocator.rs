@@ -1,56 +1,217 @@
+use crate::poet::KEYWORDS;
+use std::collections::{BTreeMap, BTreeSet};
+
 /**
- * Assigns Java identifier names to avoid collisions, keywords, and invalid characters. To use,
+ * Assigns Charj identifier names to avoid collisions, keywords, and invalid characters. To use,
  * first create an instance and allocate all of the names that you need. Typically this is a
- * mix of user-supplied names and constants: <pre>   {@code
+ * mix of user-supplied names and constants:
  *
- *   NameAllocator nameAllocator = new NameAllocator();
- *   for (MyProperty property : properties) {
- *     nameAllocator.newName(property.name(), property);
- *   }
- *   nameAllocator.newName("sb", "string builder");
- * }</pre>
+ * ```ignore
+ * let mut name_allocator = NameAllocator::new();
+ * for property in properties.iter() {
+ *     name_allocator.new_name(&property.name, &property.name);
+ * }
+ * name_allocator.new_name("sb", "string builder");
+ * ```
  *
- * Pass a unique tag object to each allocation. The tag scopes the name, and can be used to look up
- * the allocated name later. Typically the tag is the object that is being named. In the above
- * example we use {@code property} for the user-supplied property names, and {@code "string
- * builder"} for our constant string builder.
+ * Pass a unique tag to each allocation. The tag scopes the name, and can be used to look up the
+ * allocated name later with `get`. Given user-supplied properties named `ab` and `sb`, the
+ * string builder above is allocated `sb_` to avoid conflicting with the user-supplied `sb`.
  *
- * <p>Once we've allocated names we can use them when generating code: <pre>   {@code
+ * Underscores are also prefixed for names that start with a digit, and used to replace
+ * name-unsafe characters like space or dash. Charj keywords are never allocated, so a suggestion
+ * like `when` becomes `when_`.
  *
- *   MethodSpec.Builder builder = MethodSpec.methodBuilder("toString")
- *       .addAnnotation(Override.class)
- *       .addModifiers(Modifier.PUBLIC)
- *       .returns(String.class);
- *
- *   builder.addStatement("$1T $2N = new $1T()",
- *       StringBuilder.class, nameAllocator.get("string builder"));
- *   for (MyProperty property : properties) {
- *     builder.addStatement("$N.append($N)",
- *         nameAllocator.get("string builder"), nameAllocator.get(property));
- *   }
- *   builder.addStatement("return $N", nameAllocator.get("string builder"));
- *   return builder.build();
- * }</pre>
- *
- * The above code generates unique names if presented with conflicts. Given user-supplied properties
- * with names {@code ab} and {@code sb} this generates the following:  <pre>   {@code
- *
- *   &#64;Override
- *   public String toString() {
- *     StringBuilder sb_ = new StringBuilder();
- *     sb_.append(ab);
- *     sb_.append(sb);
- *     return sb_.toString();
- *   }
- * }</pre>
- *
- * The underscore is appended to {@code sb} to avoid conflicting with the user-supplied {@code sb}
- * property. Underscores are also prefixed for names that start with a digit, and used to replace
- * name-unsafe characters like space or dash.
- *
- * <p>When dealing with multiple independent inner scopes, use a {@link #clone()} of the
- * NameAllocator used for the outer scope to further refine name allocation for a specific inner
- * scope.
+ * When dealing with multiple independent inner scopes, use a `clone()` of the NameAllocator used
+ * for the outer scope to further refine name allocation for a specific inner scope.
  */
 #[derive(Serialize, Deserialize, Clone, Debug)]
-pub struct NameAllocator {}
+pub struct NameAllocator {
+    allocated_names: BTreeSet<String>,
+    tag_to_name: BTreeMap<String, String>,
+}
+
+impl NameAllocator {
+    pub fn new() -> Self {
+        NameAllocator {
+            allocated_names: BTreeSet::new(),
+            tag_to_name: BTreeMap::new(),
+        }
+    }
+
+    ///
+    /// Returns a new name based on `suggestion` that won't clash with any name allocated so far,
+    /// nor with a keyword. The name can be looked up again with `get(tag)`.
+    ///
+    pub fn new_name(&mut self, suggestion: impl AsRef<str>, tag: impl Into<String>) -> String {
+        let suggestion = suggestion.as_ref();
+        let tag = tag.into();
+        if let Some(existing) = self.tag_to_name.get(&tag) {
+            panic!(
+                "tag {} cannot be used for both '{}' and '{}'",
+                tag,
+                existing,
+                to_identifier(suggestion)
+            );
+        }
+
+        let result = self.allocate(suggestion);
+        self.tag_to_name.insert(tag, result.clone());
+        result
+    }
+
+    ///
+    /// Like `new_name`, for names that never need to be looked up again.
+    ///
+    pub fn allocate(&mut self, suggestion: impl AsRef<str>) -> String {
+        let mut result = to_identifier(suggestion.as_ref());
+        while self.allocated_names.contains(&result) || KEYWORDS.contains(&result.as_str()) {
+            result.push('_');
+        }
+
+        self.allocated_names.insert(result.clone());
+        result
+    }
+
+    ///
+    /// Marks `name` as taken without changing it, for names chosen elsewhere like parameters.
+    /// Names are tracked unescaped, so a parameter `when` or `größe` keeps a local of the same
+    /// name from shadowing it. Returns false if the name was already taken.
+    ///
+    pub fn reserve(&mut self, name: impl Into<String>) -> bool {
+        self.allocated_names.insert(name.into())
+    }
+
+    /// Marks every name taken in `other` as taken in this allocator too.
+    pub fn reserve_all(&mut self, other: &NameAllocator) {
+        self.allocated_names
+            .extend(other.allocated_names.iter().cloned());
+    }
+
+    /// Retrieves a name that was previously created with `new_name`.
+    pub fn get(&self, tag: impl AsRef<str>) -> String {
+        let tag = tag.as_ref();
+        match self.tag_to_name.get(tag) {
+            Some(name) => name.clone(),
+            None => panic!("unknown tag: {}", tag),
+        }
+    }
+}
+
+impl Default for NameAllocator {
+    fn default() -> Self {
+        NameAllocator::new()
+    }
+}
+
+///
+/// Replaces characters that can't appear in an identifier with `_`, and prefixes `_` when the
+/// identifier would otherwise start with a digit. Letters and digits outside ASCII are kept.
+///
+pub fn to_identifier(suggestion: &str) -> String {
+    let mut result = String::new();
+    for (index, c) in suggestion.chars().enumerate() {
+        if index == 0 && c.is_numeric() {
+            result.push('_');
+        }
+        if c.is_alphanumeric() || c == '_' {
+            result.push(c);
+        } else {
+            result.push('_');
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::poet::name_allocator::NameAllocator;
+
+    #[test]
+    fn usage() {
+        let mut name_allocator = NameAllocator::new();
+        assert_eq!("foo", name_allocator.new_name("foo", "1"));
+        assert_eq!("bar", name_allocator.new_name("bar", "2"));
+        assert_eq!("foo", name_allocator.get("1"));
+        assert_eq!("bar", name_allocator.get("2"));
+    }
+
+    #[test]
+    fn names_and_tags_known_only_at_runtime() {
+        let mut name_allocator = NameAllocator::new();
+        let columns = [String::from("taco"), String::from("taco")];
+        for (index, column) in columns.iter().enumerate() {
+            name_allocator.new_name(column, format!("column{}", index));
+        }
+        assert_eq!("taco", name_allocator.get(String::from("column0")));
+        assert_eq!("taco_", name_allocator.get(String::from("column1")));
+    }
+
+    #[test]
+    fn name_collision() {
+        let mut name_allocator = NameAllocator::new();
+        assert_eq!("foo", name_allocator.new_name("foo", "1"));
+        assert_eq!("foo_", name_allocator.new_name("foo", "2"));
+        assert_eq!("foo__", name_allocator.new_name("foo", "3"));
+    }
+
+    #[test]
+    fn character_mapping_substitute() {
+        let mut name_allocator = NameAllocator::new();
+        assert_eq!("a_b", name_allocator.new_name("a-b", "1"));
+        assert_eq!("a_b_", name_allocator.new_name("a b", "2"));
+        assert_eq!("naïve", name_allocator.new_name("naïve", "3"));
+        assert_eq!("taco_", name_allocator.new_name("taco🌮", "4"));
+    }
+
+    #[test]
+    fn digit_prefix() {
+        let mut name_allocator = NameAllocator::new();
+        assert_eq!("_1ab", name_allocator.new_name("1ab", "1"));
+        assert_eq!("a_1", name_allocator.new_name("a-1", "2"));
+        assert_eq!("_٣ab", name_allocator.new_name("٣ab", "3"));
+    }
+
+    #[test]
+    fn keyword_is_escaped() {
+        let mut name_allocator = NameAllocator::new();
+        assert_eq!("when_", name_allocator.new_name("when", "1"));
+        assert_eq!("struct_", name_allocator.new_name("struct", "2"));
+    }
+
+    #[test]
+    fn clone_usage() {
+        let mut outer_scope = NameAllocator::new();
+        outer_scope.new_name("foo", "1");
+
+        let mut inner_scope_1 = outer_scope.clone();
+        assert_eq!("bar", inner_scope_1.new_name("bar", "2"));
+        assert_eq!("foo_", inner_scope_1.new_name("foo", "3"));
+
+        let mut inner_scope_2 = outer_scope.clone();
+        assert_eq!("foo_", inner_scope_2.new_name("foo", "2"));
+        assert_eq!("bar", inner_scope_2.new_name("bar", "3"));
+    }
+
+    #[test]
+    fn reserved_names_are_kept() {
+        let mut name_allocator = NameAllocator::new();
+        assert!(name_allocator.reserve("count"));
+        assert!(!name_allocator.reserve("count"));
+        assert_eq!("count_", name_allocator.allocate("count"));
+    }
+
+    #[test]
+    #[should_panic(expected = "tag 1 cannot be used for both 'foo' and 'bar'")]
+    fn tag_reuse_forbidden() {
+        let mut name_allocator = NameAllocator::new();
+        name_allocator.new_name("foo", "1");
+        name_allocator.new_name("bar", "1");
+    }
+
+    #[test]
+    #[should_panic(expected = "unknown tag: 1")]
+    fn use_before_allocate_forbidden() {
+        NameAllocator::new().get("1");
+    }
+}
@@ -5,7 +5,7 @@ pub struct BaseOperator {
 }
 
 #[allow(non_camel_case_types)]
-#[derive(Hash, Eq, PartialEq, Debug, Clone)]
+#[derive(Serialize, Deserialize, Hash, Eq, PartialEq, Debug, Clone)]
 pub enum Operator {
     UNARY_PLUS,
     PLUS,
@@ -136,7 +136,7 @@ impl Operator {
         function_name: "iterator",
     };
 
-    fn value(&self) -> BaseOperator {
+    pub fn value(&self) -> BaseOperator {
         match *self {
             Operator::UNARY_PLUS => Operator::UNARY_PLUS_OPS,
             Operator::PLUS => Operator::PLUS_OPS,
@@ -165,6 +165,31 @@ impl Operator {
             Operator::ITERATOR => Operator::ITERATOR_OPS,
         }
     }
+
+    /// Number of parameters the operator function takes, not counting its receiver.
+    pub fn parameter_count(&self) -> usize {
+        match *self {
+            Operator::UNARY_PLUS
+            | Operator::UNARY_MINUS
+            | Operator::NOT
+            | Operator::INC
+            | Operator::DEC
+            | Operator::ITERATOR => 0,
+            _ => 1,
+        }
+    }
+
+    /// Augmented assignments like `+=` must return `Unit`.
+    pub fn is_assign(&self) -> bool {
+        matches!(
+            *self,
+            Operator::PLUS_ASSIGN
+                | Operator::MINUS_ASSIGN
+                | Operator::TIMES_ASSIGN
+                | Operator::DIV_ASSIGN
+                | Operator::REM_ASSIGN
+        )
+    }
 }
 
 #[cfg(test)]
@@ -176,4 +201,13 @@ mod tests {
         let operator = Operator::NOT_EQUALS;
         println!("{:?}", operator.value());
     }
+
+    #[test]
+    fn parameter_count() {
+        assert_eq!(0, Operator::UNARY_MINUS.parameter_count());
+        assert_eq!(1, Operator::MINUS.parameter_count());
+        assert_eq!(1, Operator::MINUS_ASSIGN.parameter_count());
+        assert!(Operator::MINUS_ASSIGN.is_assign());
+        assert!(!Operator::MINUS.is_assign());
+    }
 }
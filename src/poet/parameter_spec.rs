@@ -1,3 +1,194 @@
+use crate::poet::annotation_spec::AnnotationSpec;
+use crate::poet::code_block::{Arg, CodeBlock, CodeBlockBuilder};
+use crate::poet::code_writer::CodeWriter;
+use crate::poet::modifier::{check_modifiers, Modifier, Target};
+use crate::poet::type_name::TypeName;
+use crate::poet::{check_name, DEFAULT_INDENT};
+use core::fmt;
+
 /** A generated parameter declaration. */
-#[derive(Serialize, Deserialize, Clone, Debug)]
-pub struct ParameterSpec {}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ParameterSpec {
+    pub name: String,
+    pub type_name: TypeName,
+    pub modifiers: Vec<Modifier>,
+    pub annotations: Vec<AnnotationSpec>,
+    pub kdoc: CodeBlock,
+    pub default_value: Option<CodeBlock>,
+}
+
+impl ParameterSpec {
+    pub fn new(builder: &ParameterSpecBuilder) -> Self {
+        ParameterSpec {
+            name: builder.name.clone(),
+            type_name: builder.type_name.clone(),
+            modifiers: builder.modifiers.clone(),
+            annotations: builder.annotations.clone(),
+            kdoc: builder.kdoc.build(),
+            default_value: builder.default_value.clone(),
+        }
+    }
+
+    pub fn builder(name: impl Into<String>, type_name: TypeName) -> ParameterSpecBuilder {
+        ParameterSpecBuilder::new(name, type_name)
+    }
+
+    pub fn is_vararg(&self) -> bool {
+        self.modifiers.contains(&Modifier::VARARG)
+    }
+
+    pub fn emit(&self, writer: &mut CodeWriter) {
+        self.emit_annotations_and_modifiers(writer);
+        writer.emit_code("%N:·", vec![self.name.clone()]);
+        self.type_name.emit(writer);
+        self.emit_default_value(writer);
+    }
+
+    pub fn emit_annotations_and_modifiers(&self, writer: &mut CodeWriter) {
+        writer.emit_annotations(&self.annotations, true);
+        writer.emit_modifiers(&self.modifiers);
+    }
+
+    pub fn emit_default_value(&self, writer: &mut CodeWriter) {
+        if let Some(default_value) = &self.default_value {
+            writer.emit(String::from(" = "));
+            writer.emit_block(default_value);
+        }
+    }
+}
+
+impl fmt::Display for ParameterSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut out = String::new();
+        let mut writer = CodeWriter::new(&mut out, DEFAULT_INDENT);
+        self.emit(&mut writer);
+        writer.close();
+        write!(f, "{}", out)
+    }
+}
+
+///
+/// Emits a parenthesized, comma-separated parameter list. `emit_parameter` renders a single
+/// parameter, which lets primary constructors emit promoted properties in place of parameters.
+/// A list that doesn't fit on its line, including what `rest_of_line` writes after the closing
+/// `)`, gets one parameter per line.
+///
+pub fn emit_parameters<F, R>(
+    writer: &mut CodeWriter,
+    parameters: &[ParameterSpec],
+    mut emit_parameter: F,
+    rest_of_line: R,
+) where
+    F: FnMut(&mut CodeWriter, &ParameterSpec),
+    R: FnOnce(&mut CodeWriter),
+{
+    writer.emit(String::from("("));
+    writer.emit_group(
+        parameters.len(),
+        |writer, index| emit_parameter(writer, &parameters[index]),
+        |writer| {
+            writer.emit(String::from(")"));
+            rest_of_line(writer);
+        },
+    );
+    writer.emit(String::from(")"));
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ParameterSpecBuilder {
+    pub name: String,
+    pub type_name: TypeName,
+    pub modifiers: Vec<Modifier>,
+    pub annotations: Vec<AnnotationSpec>,
+    pub kdoc: CodeBlockBuilder,
+    pub default_value: Option<CodeBlock>,
+}
+
+impl ParameterSpecBuilder {
+    pub fn new(name: impl Into<String>, type_name: TypeName) -> Self {
+        ParameterSpecBuilder {
+            name: name.into(),
+            type_name,
+            modifiers: vec![],
+            annotations: vec![],
+            kdoc: CodeBlockBuilder::new(),
+            default_value: None,
+        }
+    }
+
+    pub fn add_modifiers(&mut self, modifiers: Vec<Modifier>) -> &mut ParameterSpecBuilder {
+        self.modifiers.extend(modifiers);
+        self
+    }
+
+    pub fn add_annotation(&mut self, annotation: AnnotationSpec) -> &mut ParameterSpecBuilder {
+        self.annotations.push(annotation);
+        self
+    }
+
+    pub fn add_kdoc<T: Into<Arg>>(
+        &mut self,
+        format: &str,
+        args: Vec<T>,
+    ) -> &mut ParameterSpecBuilder {
+        self.kdoc.add(format, args);
+        self
+    }
+
+    pub fn default_value<T: Into<Arg>>(
+        &mut self,
+        format: &str,
+        args: Vec<T>,
+    ) -> &mut ParameterSpecBuilder {
+        self.default_value = Some(CodeBlock::of(format, args));
+        self
+    }
+
+    pub fn build(&self) -> ParameterSpec {
+        check_name(&self.name);
+        let declaration = format!("parameter {}", self.name);
+        check_modifiers(&self.modifiers, Target::PARAMETER, &declaration);
+        ParameterSpec::new(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::poet::annotation_spec::AnnotationSpec;
+    use crate::poet::class_name::StructName;
+    use crate::poet::modifier::Modifier;
+    use crate::poet::parameter_spec::ParameterSpec;
+    use crate::poet::type_name::TypeName;
+
+    #[test]
+    #[should_panic(expected = "can't escape identifier \"a`b\": it contains '`'")]
+    fn invalid_name() {
+        ParameterSpec::builder("a`b", TypeName::get("", "Int")).build();
+    }
+
+    #[test]
+    fn default_value() {
+        let parameter = ParameterSpec::builder("count", TypeName::get("", "Int"))
+            .default_value("%L", vec!["1"])
+            .build();
+        assert_eq!("count: Int = 1", format!("{}", parameter));
+    }
+
+    #[test]
+    fn annotations_and_modifiers() {
+        let parameter = ParameterSpec::builder("fillings", TypeName::get("", "String"))
+            .add_annotation(AnnotationSpec::builder(StructName::new("", "Fresh")).build())
+            .add_modifiers(vec![Modifier::VARARG])
+            .build();
+        assert!(parameter.is_vararg());
+        assert_eq!("@Fresh vararg fillings: String", format!("{}", parameter));
+    }
+
+    #[test]
+    #[should_panic(expected = "modifier PRIVATE is not allowed on parameter count")]
+    fn visibility_is_not_a_parameter_modifier() {
+        ParameterSpec::builder("count", TypeName::get("", "Int"))
+            .add_modifiers(vec![Modifier::PRIVATE])
+            .build();
+    }
+}
@@ -1,2 +1,49 @@
-#[derive(Serialize, Deserialize, Clone, Debug)]
-pub struct ParameterizedTypeName {}
+use crate::poet::class_name::StructName;
+use crate::poet::code_writer::CodeWriter;
+use crate::poet::type_name::TypeName;
+use core::fmt;
+
+///
+/// A generic type applied to its type arguments, like `Map<String, Taco>`.
+///
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ParameterizedTypeName {
+    pub raw_type: StructName,
+    pub type_arguments: Vec<TypeName>,
+}
+
+impl ParameterizedTypeName {
+    pub fn get(raw_type: StructName, type_arguments: Vec<TypeName>) -> Self {
+        if type_arguments.is_empty() {
+            panic!("no type arguments: {}", raw_type);
+        }
+        ParameterizedTypeName {
+            raw_type,
+            type_arguments,
+        }
+    }
+
+    pub fn emit(&self, writer: &mut CodeWriter) {
+        let name = writer.lookup_name(&self.raw_type);
+        writer.emit(name);
+        writer.emit(String::from("<"));
+        for (index, type_argument) in self.type_arguments.iter().enumerate() {
+            if index > 0 {
+                writer.emit(String::from(", "));
+            }
+            type_argument.emit(writer);
+        }
+        writer.emit(String::from(">"));
+    }
+}
+
+impl fmt::Display for ParameterizedTypeName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let type_arguments: Vec<String> = self
+            .type_arguments
+            .iter()
+            .map(|type_argument| type_argument.to_string())
+            .collect();
+        write!(f, "{}<{}>", self.raw_type, type_arguments.join(", "))
+    }
+}
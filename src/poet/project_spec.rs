@@ -0,0 +1,317 @@
+use crate::poet::file_spec::{write_atomically, FileSpec, WriteSummary};
+use std::collections::BTreeSet;
+use std::fs;
+use std::io;
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+
+/// The file in the output directory listing the files the last run generated.
+pub const MANIFEST_FILE_NAME: &str = ".charjpoet-manifest";
+
+///
+/// A set of generated files that are written to one directory together. Writing records the
+/// generated paths in a manifest, and files listed in the previous manifest that are no longer
+/// generated are deleted, so renamed or removed types don't leave stale sources behind.
+///
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ProjectSpec {
+    pub files: Vec<FileSpec>,
+}
+
+impl ProjectSpec {
+    pub fn new(builder: &mut ProjectSpecBuilder) -> Self {
+        ProjectSpec {
+            files: std::mem::take(&mut builder.files),
+        }
+    }
+
+    pub fn builder() -> ProjectSpecBuilder {
+        ProjectSpecBuilder::new()
+    }
+
+    ///
+    /// Writes every file under `directory`, skipping files whose content is unchanged, then
+    /// deletes the files of the previous run that weren't written this time and updates the
+    /// manifest. A stale entry that is no longer a regular file, like a directory that took its
+    /// place, is left alone.
+    ///
+    pub fn write_to_dir(&self, directory: &Path) -> io::Result<WriteSummary> {
+        let manifest_path = directory.join(MANIFEST_FILE_NAME);
+        let previous = read_manifest(&manifest_path)?;
+
+        let mut summary = WriteSummary::default();
+        let mut generated: BTreeSet<String> = BTreeSet::new();
+        for file in self.files.iter() {
+            let (path, outcome) = file.write_to_dir_if_changed(directory)?;
+            summary.record(outcome);
+            generated.insert(manifest_entry(directory, &path)?);
+        }
+
+        for stale in previous.difference(&generated) {
+            let path = directory.join(stale);
+            match fs::symlink_metadata(&path) {
+                Ok(metadata) if metadata.is_file() => {}
+                Ok(_) => continue,
+                Err(error) if error.kind() == io::ErrorKind::NotFound => continue,
+                Err(error) => return Err(error),
+            }
+            fs::remove_file(&path)?;
+            summary.deleted += 1;
+            remove_empty_parents(directory, &path);
+        }
+
+        write_atomically(&manifest_path, |out| {
+            for entry in generated.iter() {
+                writeln!(out, "{}", entry)?;
+            }
+            Ok(())
+        })?;
+        Ok(summary)
+    }
+}
+
+///
+/// Returns the entries of the manifest at `path`, or nothing if there is none yet. Fails if an
+/// entry would point outside the output directory, so a tampered manifest can't delete other
+/// files.
+///
+fn read_manifest(path: &Path) -> io::Result<BTreeSet<String>> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(BTreeSet::new()),
+        Err(error) => return Err(error),
+    };
+    let mut entries = BTreeSet::new();
+    for line in content.lines().filter(|line| !line.is_empty()) {
+        let is_inside = Path::new(line)
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)));
+        if !is_inside {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("manifest entry {} is not inside {}", line, path.display()),
+            ));
+        }
+        entries.insert(String::from(line));
+    }
+    Ok(entries)
+}
+
+///
+/// Returns `path` relative to `directory`, with `/` separators on every platform. Fails if `path`
+/// isn't inside `directory`.
+///
+fn manifest_entry(directory: &Path, path: &Path) -> io::Result<String> {
+    let relative = path.strip_prefix(directory).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} is not inside {}", path.display(), directory.display()),
+        )
+    })?;
+    let segments: Vec<String> = relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy().into_owned())
+        .collect();
+    Ok(segments.join("/"))
+}
+
+/// Removes the directories between `path` and `directory` that are left empty.
+fn remove_empty_parents(directory: &Path, path: &Path) {
+    let mut parent: Option<PathBuf> = path.parent().map(Path::to_path_buf);
+    while let Some(current) = parent {
+        if current == directory || !current.starts_with(directory) {
+            break;
+        }
+        if fs::remove_dir(&current).is_err() {
+            break;
+        }
+        parent = current.parent().map(Path::to_path_buf);
+    }
+}
+
+#[derive(Serialize, Debug, Default)]
+pub struct ProjectSpecBuilder {
+    pub files: Vec<FileSpec>,
+}
+
+impl ProjectSpecBuilder {
+    pub fn new() -> Self {
+        ProjectSpecBuilder { files: vec![] }
+    }
+
+    pub fn add_file(&mut self, file: FileSpec) -> &mut ProjectSpecBuilder {
+        self.files.push(file);
+        self
+    }
+
+    pub fn add_files(&mut self, files: Vec<FileSpec>) -> &mut ProjectSpecBuilder {
+        for file in files {
+            self.add_file(file);
+        }
+        self
+    }
+
+    pub fn build(&mut self) -> ProjectSpec {
+        let mut seen: BTreeSet<(&str, &str)> = BTreeSet::new();
+        for file in self.files.iter() {
+            if !seen.insert((file.package_name(), file.name())) {
+                panic!(
+                    "duplicate file {} in package {:?}",
+                    file.name(),
+                    file.package_name()
+                );
+            }
+        }
+        ProjectSpec::new(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::poet::file_spec::{FileSpec, FileSpecBuilder, WriteSummary};
+    use crate::poet::project_spec::{manifest_entry, ProjectSpec, MANIFEST_FILE_NAME};
+    use crate::poet::type_spec::TypeSpec;
+    use std::path::Path;
+    use std::{env, fs, io, process};
+
+    fn file(package_name: &str, name: &str) -> FileSpec {
+        FileSpecBuilder::new(package_name, name)
+            .add_type(TypeSpec::class_builder(name).build())
+            .build()
+    }
+
+    #[test]
+    fn writes_files_and_deletes_stale_ones() {
+        let directory = env::temp_dir().join(format!("charjpoet-project-{}", process::id()));
+
+        let summary = ProjectSpec::builder()
+            .add_file(file("com.example.food", "Taco"))
+            .add_file(file("com.example.drink", "Horchata"))
+            .build()
+            .write_to_dir(&directory)
+            .unwrap();
+        assert_eq!(
+            WriteSummary {
+                created: 2,
+                ..WriteSummary::default()
+            },
+            summary
+        );
+        assert_eq!(
+            "com/example/drink/Horchata.cj\ncom/example/food/Taco.cj\n",
+            fs::read_to_string(directory.join(MANIFEST_FILE_NAME)).unwrap()
+        );
+
+        let summary = ProjectSpec::builder()
+            .add_file(file("com.example.food", "Taco"))
+            .add_file(file("com.example.food", "Burrito"))
+            .build()
+            .write_to_dir(&directory)
+            .unwrap();
+        assert_eq!(
+            WriteSummary {
+                created: 1,
+                unchanged: 1,
+                deleted: 1,
+                ..WriteSummary::default()
+            },
+            summary
+        );
+        assert!(!directory.join("com/example/drink").exists());
+        assert!(directory.join("com/example/food/Burrito.cj").exists());
+        assert_eq!(
+            "com/example/food/Burrito.cj\ncom/example/food/Taco.cj\n",
+            fs::read_to_string(directory.join(MANIFEST_FILE_NAME)).unwrap()
+        );
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn rejects_manifest_entries_outside_the_directory() {
+        let directory = env::temp_dir().join(format!("charjpoet-manifest-{}", process::id()));
+        let outside = env::temp_dir().join(format!("charjpoet-outside-{}.cj", process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(&outside, "keep me").unwrap();
+        let entry = format!("../{}", outside.file_name().unwrap().to_string_lossy());
+        fs::write(directory.join(MANIFEST_FILE_NAME), entry).unwrap();
+
+        let error = ProjectSpec::builder()
+            .add_file(file("com.example", "Taco"))
+            .build()
+            .write_to_dir(&directory)
+            .unwrap_err();
+
+        assert_eq!(io::ErrorKind::InvalidData, error.kind());
+        assert!(outside.exists());
+        assert!(!directory.join("com/example/Taco.cj").exists());
+        fs::remove_file(&outside).unwrap();
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn rejects_tampered_manifest() {
+        let directory = env::temp_dir().join(format!("charjpoet-tampered-{}", process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let manifest = "com/example/Taco.cj\n/etc/passwd\n";
+        fs::write(directory.join(MANIFEST_FILE_NAME), manifest).unwrap();
+
+        let error = ProjectSpec::builder()
+            .build()
+            .write_to_dir(&directory)
+            .unwrap_err();
+
+        assert_eq!(io::ErrorKind::InvalidData, error.kind());
+        assert_eq!(
+            manifest,
+            fs::read_to_string(directory.join(MANIFEST_FILE_NAME)).unwrap()
+        );
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn skips_stale_entries_that_are_no_longer_files() {
+        let directory = env::temp_dir().join(format!("charjpoet-stale-dir-{}", process::id()));
+        ProjectSpec::builder()
+            .add_file(file("com.example", "Taco"))
+            .build()
+            .write_to_dir(&directory)
+            .unwrap();
+        let stale = directory.join("com/example/Taco.cj");
+        fs::remove_file(&stale).unwrap();
+        fs::create_dir(&stale).unwrap();
+
+        let summary = ProjectSpec::builder()
+            .add_file(file("com.example", "Burrito"))
+            .build()
+            .write_to_dir(&directory)
+            .unwrap();
+
+        assert_eq!(0, summary.deleted);
+        assert!(stale.is_dir());
+        assert_eq!(
+            "com/example/Burrito.cj\n",
+            fs::read_to_string(directory.join(MANIFEST_FILE_NAME)).unwrap()
+        );
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "duplicate file Taco in package \"com.example\"")]
+    fn duplicate_files() {
+        ProjectSpec::builder()
+            .add_file(file("com.example", "Taco"))
+            .add_file(file("com.example", "Taco"))
+            .build();
+    }
+
+    #[test]
+    fn manifest_entry_requires_path_inside_directory() {
+        let directory = Path::new("out");
+        assert_eq!(
+            "com/example/Taco.cj",
+            manifest_entry(directory, &directory.join("com/example/Taco.cj")).unwrap()
+        );
+        let error = manifest_entry(directory, Path::new("elsewhere/Taco.cj")).unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidInput, error.kind());
+    }
+}
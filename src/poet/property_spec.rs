@@ -1,3 +1,455 @@
+use crate::poet::annotation_spec::AnnotationSpec;
+use crate::poet::code_block::{Arg, CodeBlock, CodeBlockBuilder};
+use crate::poet::code_writer::CodeWriter;
+use crate::poet::member_name::MemberName;
+use crate::poet::method_spec::{MethodSpec, GETTER, SETTER};
+use crate::poet::modifier::{check_modifiers, Modifier, Target};
+use crate::poet::type_name::TypeName;
+use crate::poet::{check_name, DEFAULT_INDENT};
+use core::fmt;
+
 /** A generated property declaration. */
 #[derive(Serialize, Deserialize, Clone, Debug)]
-pub struct PropertySpec {}
+pub struct PropertySpec {
+    pub mutable: bool,
+    pub name: String,
+    pub type_name: TypeName,
+    pub modifiers: Vec<Modifier>,
+    pub annotations: Vec<AnnotationSpec>,
+    pub initializer: Option<CodeBlock>,
+    pub delegate: Option<CodeBlock>,
+    pub getter: Option<MethodSpec>,
+    pub setter: Option<MethodSpec>,
+    pub receiver_type: Option<TypeName>,
+    pub kdoc: CodeBlock,
+}
+
+impl PropertySpec {
+    pub fn new(builder: &PropertySpecBuilder) -> Self {
+        PropertySpec {
+            mutable: builder.mutable,
+            name: builder.name.clone(),
+            type_name: builder.type_name.clone(),
+            modifiers: builder.modifiers.clone(),
+            annotations: builder.annotations.clone(),
+            initializer: builder.initializer.clone(),
+            delegate: builder.delegate.clone(),
+            getter: builder.getter.clone(),
+            setter: builder.setter.clone(),
+            receiver_type: builder.receiver_type.clone(),
+            kdoc: builder.kdoc.build(),
+        }
+    }
+
+    pub fn builder(name: impl Into<String>, type_name: TypeName) -> PropertySpecBuilder {
+        PropertySpecBuilder::new(name, type_name)
+    }
+
+    ///
+    /// Returns a [MemberName] for this property declared at the top level of `package_name`, to be
+    /// referenced with `%M`. Properties with a receiver produce extension members.
+    ///
+    pub fn member_name(&self, package_name: impl Into<String>) -> MemberName {
+        if self.receiver_type.is_some() {
+            return MemberName::extension(package_name, &self.name);
+        }
+        MemberName::new(package_name, &self.name)
+    }
+
+    ///
+    /// Emits this property. With `inline` set the property is written as a primary constructor
+    /// parameter: no initializer, accessors or trailing newline.
+    ///
+    pub fn emit(&self, writer: &mut CodeWriter, inline: bool) {
+        if !inline {
+            writer.emit_kdoc(&self.kdoc);
+        }
+        writer.emit_annotations(&self.annotations, inline);
+        writer.emit_modifiers(&self.modifiers);
+        let keyword = if self.mutable { "var" } else { "val" };
+        writer.emit(format!("{} ", keyword));
+        if let Some(receiver_type) = &self.receiver_type {
+            receiver_type.emit(writer);
+            writer.emit(String::from("."));
+        }
+        writer.emit_code("%N:·", vec![self.name.clone()]);
+        self.type_name.emit(writer);
+        if inline {
+            return;
+        }
+
+        if let Some(initializer) = &self.initializer {
+            writer.emit(String::from(" = "));
+            writer.emit_block(initializer);
+        } else if let Some(delegate) = &self.delegate {
+            writer.emit(String::from(" by "));
+            writer.emit_block(delegate);
+        }
+        writer.emit(String::from("\n"));
+
+        for accessor in [&self.getter, &self.setter].iter().copied().flatten() {
+            writer.indent(1);
+            accessor.emit(writer);
+            writer.unindent(1);
+        }
+    }
+}
+
+impl fmt::Display for PropertySpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut out = String::new();
+        let mut writer = CodeWriter::new(&mut out, DEFAULT_INDENT);
+        self.emit(&mut writer, false);
+        writer.close();
+        write!(f, "{}", out)
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct PropertySpecBuilder {
+    pub mutable: bool,
+    pub name: String,
+    pub type_name: TypeName,
+    pub modifiers: Vec<Modifier>,
+    pub annotations: Vec<AnnotationSpec>,
+    pub initializer: Option<CodeBlock>,
+    pub delegate: Option<CodeBlock>,
+    pub getter: Option<MethodSpec>,
+    pub setter: Option<MethodSpec>,
+    pub receiver_type: Option<TypeName>,
+    pub kdoc: CodeBlockBuilder,
+}
+
+impl PropertySpecBuilder {
+    pub fn new(name: impl Into<String>, type_name: TypeName) -> Self {
+        PropertySpecBuilder {
+            mutable: false,
+            name: name.into(),
+            type_name,
+            modifiers: vec![],
+            annotations: vec![],
+            initializer: None,
+            delegate: None,
+            getter: None,
+            setter: None,
+            receiver_type: None,
+            kdoc: CodeBlockBuilder::new(),
+        }
+    }
+
+    pub fn mutable(&mut self, mutable: bool) -> &mut PropertySpecBuilder {
+        self.mutable = mutable;
+        self
+    }
+
+    pub fn add_kdoc<T: Into<Arg>>(
+        &mut self,
+        format: &str,
+        args: Vec<T>,
+    ) -> &mut PropertySpecBuilder {
+        self.kdoc.add(format, args);
+        self
+    }
+
+    pub fn add_annotation(&mut self, annotation: AnnotationSpec) -> &mut PropertySpecBuilder {
+        self.annotations.push(annotation);
+        self
+    }
+
+    pub fn add_modifiers(&mut self, modifiers: Vec<Modifier>) -> &mut PropertySpecBuilder {
+        self.modifiers.extend(modifiers);
+        self
+    }
+
+    ///
+    /// Makes this an extension property on `receiver_type`, emitted as `val Receiver.name`.
+    ///
+    pub fn receiver(&mut self, receiver_type: TypeName) -> &mut PropertySpecBuilder {
+        self.receiver_type = Some(receiver_type);
+        self
+    }
+
+    pub fn initializer<T: Into<Arg>>(
+        &mut self,
+        format: &str,
+        args: Vec<T>,
+    ) -> &mut PropertySpecBuilder {
+        self.initializer = Some(CodeBlock::of(format, args));
+        self
+    }
+
+    ///
+    /// Delegates the property to the given expression, emitted as `val name: Type by delegate`.
+    ///
+    pub fn delegate<T: Into<Arg>>(
+        &mut self,
+        format: &str,
+        args: Vec<T>,
+    ) -> &mut PropertySpecBuilder {
+        self.delegate = Some(CodeBlock::of(format, args));
+        self
+    }
+
+    pub fn getter(&mut self, getter: MethodSpec) -> &mut PropertySpecBuilder {
+        if getter.name != GETTER {
+            panic!("{} is not a getter", getter.name);
+        }
+        if !getter.parameters.is_empty() {
+            panic!("getter of {} cannot have parameters", self.name);
+        }
+        self.getter = Some(getter);
+        self
+    }
+
+    pub fn setter(&mut self, setter: MethodSpec) -> &mut PropertySpecBuilder {
+        if setter.name != SETTER {
+            panic!("{} is not a setter", setter.name);
+        }
+        if setter.parameters.len() > 1 {
+            panic!("setter of {} can have at most one parameter", self.name);
+        }
+        self.setter = Some(setter);
+        self
+    }
+
+    pub fn build(&self) -> PropertySpec {
+        check_name(&self.name);
+        let declaration = format!("property {}", self.name);
+        check_modifiers(&self.modifiers, Target::PROPERTY, &declaration);
+
+        let has_accessors = self.getter.is_some() || self.setter.is_some();
+        if self.receiver_type.is_some() && self.initializer.is_some() {
+            panic!(
+                "extension property {} cannot have an initializer",
+                self.name
+            );
+        }
+        if self.setter.is_some() && !self.mutable {
+            panic!(
+                "only a mutable property can have a setter, {} is immutable",
+                self.name
+            );
+        }
+        if self.initializer.is_some() && self.delegate.is_some() {
+            panic!(
+                "property {} can't have both an initializer and a delegate",
+                self.name
+            );
+        }
+        if self.delegate.is_some() && has_accessors {
+            panic!(
+                "delegated property {} can't have custom accessors",
+                self.name
+            );
+        }
+
+        if self.modifiers.contains(&Modifier::CONST) {
+            if self.mutable {
+                panic!("const property {} must be immutable", self.name);
+            }
+            if self.initializer.is_none() {
+                panic!("const property {} must have an initializer", self.name);
+            }
+            if self.getter.is_some() {
+                panic!("const property {} can't have a custom getter", self.name);
+            }
+        }
+
+        if self.modifiers.contains(&Modifier::LATEINIT) {
+            if !self.mutable {
+                panic!("lateinit property {} must be mutable", self.name);
+            }
+            if self.initializer.is_some() || self.delegate.is_some() {
+                panic!(
+                    "lateinit property {} can't have an initializer or delegate",
+                    self.name
+                );
+            }
+            if has_accessors {
+                panic!(
+                    "lateinit property {} can't have custom accessors",
+                    self.name
+                );
+            }
+        }
+
+        PropertySpec::new(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::poet::annotation_spec::{AnnotationSpec, UseSiteTarget};
+    use crate::poet::class_name::StructName;
+    use crate::poet::method_spec::MethodSpec;
+    use crate::poet::modifier::Modifier;
+    use crate::poet::parameter_spec::ParameterSpec;
+    use crate::poet::property_spec::PropertySpec;
+    use crate::poet::type_name::TypeName;
+
+    #[test]
+    #[should_panic(expected = "can't escape identifier \"taco\\nburrito\": it contains '\\n'")]
+    fn invalid_name() {
+        PropertySpec::builder("taco\nburrito", TypeName::get("", "Int")).build();
+    }
+
+    #[test]
+    fn extension_property() {
+        let property = PropertySpec::builder("size", TypeName::get("", "Int"))
+            .receiver(TypeName::get("com.phodal", "Taco"))
+            .build();
+        assert_eq!("val com.phodal.Taco.size: Int\n", format!("{}", property));
+        assert!(property.member_name("com.phodal").is_extension);
+    }
+
+    #[test]
+    #[should_panic(expected = "extension property size cannot have an initializer")]
+    fn extension_property_without_initializer() {
+        PropertySpec::builder("size", TypeName::get("", "Int"))
+            .receiver(TypeName::get("com.phodal", "Taco"))
+            .initializer("%L", vec!["0"])
+            .build();
+    }
+
+    #[test]
+    fn use_site_targeted_annotations() {
+        let property = PropertySpec::builder("taco", TypeName::get("", "String"))
+            .add_annotation(
+                AnnotationSpec::builder(StructName::new("", "Inject"))
+                    .use_site_target(UseSiteTarget::FIELD)
+                    .build(),
+            )
+            .add_annotation(
+                AnnotationSpec::builder(StructName::new("", "Fresh"))
+                    .use_site_target(UseSiteTarget::GET)
+                    .build(),
+            )
+            .mutable(true)
+            .add_modifiers(vec![Modifier::LATEINIT])
+            .build();
+
+        assert_eq!(
+            "@field:Inject\n@get:Fresh\nlateinit var taco: String\n",
+            format!("{}", property)
+        );
+    }
+
+    #[test]
+    fn mutable_property_with_accessors() {
+        let getter = MethodSpec::getter_builder()
+            .add_statement("return %N", vec!["field"])
+            .build();
+        let setter = MethodSpec::setter_builder()
+            .add_modifiers(vec![Modifier::PRIVATE])
+            .build();
+        let property = PropertySpec::builder("count", TypeName::get("", "Int"))
+            .mutable(true)
+            .initializer("%L", vec!["0"])
+            .getter(getter)
+            .setter(setter)
+            .build();
+
+        assert_eq!(
+            "var count: Int = 0
+    get() {
+        return field
+    }
+    private set
+",
+            format!("{}", property)
+        );
+    }
+
+    #[test]
+    fn setter_with_parameter() {
+        let setter = MethodSpec::setter_builder()
+            .add_parameter(ParameterSpec::builder("value", TypeName::get("", "Int")).build())
+            .add_statement("field = %N", vec!["value"])
+            .build();
+        let property = PropertySpec::builder("count", TypeName::get("", "Int"))
+            .mutable(true)
+            .initializer("%L", vec!["0"])
+            .setter(setter)
+            .build();
+
+        assert_eq!(
+            "var count: Int = 0\n    set(value) {\n        field = value\n    }\n",
+            format!("{}", property)
+        );
+    }
+
+    #[test]
+    fn delegated_property() {
+        let property = PropertySpec::builder("taco", TypeName::get("", "String"))
+            .delegate("lazy·{ %S }", vec!["taco"])
+            .build();
+        assert_eq!(
+            "val taco: String by lazy { \"taco\" }\n",
+            format!("{}", property)
+        );
+    }
+
+    #[test]
+    fn const_and_lateinit() {
+        let answer = PropertySpec::builder("ANSWER", TypeName::get("", "Int"))
+            .add_modifiers(vec![Modifier::CONST])
+            .initializer("%L", vec!["42"])
+            .build();
+        assert_eq!("const val ANSWER: Int = 42\n", format!("{}", answer));
+
+        let taco = PropertySpec::builder("taco", TypeName::get("", "String"))
+            .add_modifiers(vec![Modifier::LATEINIT])
+            .mutable(true)
+            .build();
+        assert_eq!("lateinit var taco: String\n", format!("{}", taco));
+    }
+
+    #[test]
+    #[should_panic(expected = "only a mutable property can have a setter, count is immutable")]
+    fn setter_requires_mutable_property() {
+        PropertySpec::builder("count", TypeName::get("", "Int"))
+            .setter(MethodSpec::setter_builder().build())
+            .build();
+    }
+
+    #[test]
+    #[should_panic(expected = "property count can't have both an initializer and a delegate")]
+    fn initializer_and_delegate_are_exclusive() {
+        PropertySpec::builder("count", TypeName::get("", "Int"))
+            .initializer("%L", vec!["0"])
+            .delegate("lazy·{ %L }", vec!["0"])
+            .build();
+    }
+
+    #[test]
+    #[should_panic(expected = "const property count must be immutable")]
+    fn const_property_must_be_immutable() {
+        PropertySpec::builder("count", TypeName::get("", "Int"))
+            .add_modifiers(vec![Modifier::CONST])
+            .mutable(true)
+            .initializer("%L", vec!["0"])
+            .build();
+    }
+
+    #[test]
+    #[should_panic(expected = "lateinit property count can't have an initializer or delegate")]
+    fn lateinit_property_has_no_initializer() {
+        PropertySpec::builder("count", TypeName::get("", "Int"))
+            .add_modifiers(vec![Modifier::LATEINIT])
+            .mutable(true)
+            .initializer("%L", vec!["0"])
+            .build();
+    }
+
+    #[test]
+    fn kdoc() {
+        let property = PropertySpec::builder("taco", TypeName::get("", "String"))
+            .add_kdoc("The taco of the day.\n", Vec::<String>::new())
+            .initializer("%S", vec!["carnitas"])
+            .build();
+        assert_eq!(
+            "/**\n * The taco of the day.\n */\nval taco: String = \"carnitas\"\n",
+            format!("{}", property)
+        );
+    }
+}
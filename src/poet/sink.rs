@@ -0,0 +1,89 @@
+use std::fmt;
+use std::io;
+
+///
+/// Adapts an [io::Write], like a file, socket or compressor, into the [fmt::Write] sink that a
+/// [CodeWriter][crate::poet::code_writer::CodeWriter] writes to. A [fmt::Error] can't carry the
+/// cause, so the first I/O error is kept and returned by [IoSink::finish].
+///
+pub struct IoSink<W: io::Write> {
+    inner: W,
+    error: Option<io::Error>,
+}
+
+impl<W: io::Write> IoSink<W> {
+    pub fn new(inner: W) -> Self {
+        IoSink { inner, error: None }
+    }
+
+    ///
+    /// Flushes the underlying writer and returns it, or the first error writing to it failed with.
+    ///
+    pub fn finish(mut self) -> io::Result<W> {
+        if let Some(error) = self.error.take() {
+            return Err(error);
+        }
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: io::Write> fmt::Write for IoSink<W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        if self.error.is_some() {
+            return Err(fmt::Error);
+        }
+        self.inner.write_all(s.as_bytes()).map_err(|error| {
+            self.error = Some(error);
+            fmt::Error
+        })
+    }
+}
+
+/// A sink that discards everything written to it, for passes that only collect information.
+#[derive(Default)]
+pub struct NullSink;
+
+impl fmt::Write for NullSink {
+    fn write_str(&mut self, _s: &str) -> fmt::Result {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::poet::sink::IoSink;
+    use std::fmt::Write;
+    use std::io;
+
+    #[test]
+    fn writes_utf8_bytes() {
+        let mut sink = IoSink::new(Vec::new());
+        write!(sink, "val taco = {:?}", "🌮").unwrap();
+        assert_eq!(
+            "val taco = \"🌮\"".as_bytes(),
+            sink.finish().unwrap().as_slice()
+        );
+    }
+
+    #[derive(Debug)]
+    struct Broken;
+
+    impl io::Write for Broken {
+        fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+            Err(io::Error::new(io::ErrorKind::BrokenPipe, "closed"))
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn keeps_first_io_error() {
+        let mut sink = IoSink::new(Broken);
+        assert!(sink.write_str("taco").is_err());
+        assert!(sink.write_str("burrito").is_err());
+        assert_eq!(io::ErrorKind::BrokenPipe, sink.finish().unwrap_err().kind());
+    }
+}
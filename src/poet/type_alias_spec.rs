@@ -1,3 +1,168 @@
+use crate::poet::class_name::StructName;
+use crate::poet::code_block::{Arg, CodeBlock, CodeBlockBuilder};
+use crate::poet::code_writer::CodeWriter;
+use crate::poet::modifier::{check_modifiers, Modifier, Target};
+use crate::poet::type_name::TypeName;
+use crate::poet::type_variable_name::{emit_type_variables, TypeVariableName};
+use crate::poet::{check_name, DEFAULT_INDENT};
+use core::fmt;
+
 /** A generated typealias declaration */
-#[derive(Serialize, Deserialize, Clone, Debug)]
-pub struct TypeAliasSpec {}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TypeAliasSpec {
+    pub name: String,
+    pub type_name: TypeName,
+    pub modifiers: Vec<Modifier>,
+    pub type_variables: Vec<TypeVariableName>,
+    pub kdoc: CodeBlock,
+}
+
+impl TypeAliasSpec {
+    pub fn new(builder: &TypeAliasSpecBuilder) -> Self {
+        TypeAliasSpec {
+            name: builder.name.clone(),
+            type_name: builder.type_name.clone(),
+            modifiers: builder.modifiers.clone(),
+            type_variables: builder.type_variables.clone(),
+            kdoc: builder.kdoc.build(),
+        }
+    }
+
+    pub fn builder(name: impl Into<String>, type_name: TypeName) -> TypeAliasSpecBuilder {
+        TypeAliasSpecBuilder::new(name, type_name)
+    }
+
+    ///
+    /// Returns the name to reference this alias with when it is declared in `package_name`. Passing
+    /// it as a `%T` argument imports the alias instead of the type it stands for.
+    ///
+    pub fn struct_name(&self, package_name: impl Into<String>) -> StructName {
+        StructName::new(package_name, &self.name)
+    }
+
+    pub fn emit(&self, writer: &mut CodeWriter) {
+        writer.emit_kdoc(&self.kdoc);
+        writer.emit_modifiers(&self.modifiers);
+        writer.emit_code("typealias %N", vec![self.name.clone()]);
+        emit_type_variables(writer, &self.type_variables);
+        writer.emit(String::from(" = "));
+        self.type_name.emit(writer);
+        writer.emit(String::from("\n"));
+    }
+}
+
+impl fmt::Display for TypeAliasSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut out = String::new();
+        let mut writer = CodeWriter::new(&mut out, DEFAULT_INDENT);
+        self.emit(&mut writer);
+        writer.close();
+        write!(f, "{}", out)
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct TypeAliasSpecBuilder {
+    pub name: String,
+    pub type_name: TypeName,
+    pub modifiers: Vec<Modifier>,
+    pub type_variables: Vec<TypeVariableName>,
+    pub kdoc: CodeBlockBuilder,
+}
+
+impl TypeAliasSpecBuilder {
+    pub fn new(name: impl Into<String>, type_name: TypeName) -> Self {
+        TypeAliasSpecBuilder {
+            name: name.into(),
+            type_name,
+            modifiers: vec![],
+            type_variables: vec![],
+            kdoc: CodeBlockBuilder::new(),
+        }
+    }
+
+    pub fn add_modifiers(&mut self, modifiers: Vec<Modifier>) -> &mut TypeAliasSpecBuilder {
+        self.modifiers.extend(modifiers);
+        self
+    }
+
+    pub fn add_type_variable(
+        &mut self,
+        type_variable: TypeVariableName,
+    ) -> &mut TypeAliasSpecBuilder {
+        self.type_variables.push(type_variable);
+        self
+    }
+
+    pub fn add_kdoc<T: Into<Arg>>(
+        &mut self,
+        format: &str,
+        args: Vec<T>,
+    ) -> &mut TypeAliasSpecBuilder {
+        self.kdoc.add(format, args);
+        self
+    }
+
+    pub fn build(&self) -> TypeAliasSpec {
+        check_name(&self.name);
+        let declaration = format!("type alias {}", self.name);
+        check_modifiers(&self.modifiers, Target::TYPE_ALIAS, &declaration);
+        TypeAliasSpec::new(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::poet::class_name::StructName;
+    use crate::poet::modifier::Modifier;
+    use crate::poet::parameterized_type_name::ParameterizedTypeName;
+    use crate::poet::type_alias_spec::TypeAliasSpec;
+    use crate::poet::type_name::TypeName;
+    use crate::poet::type_variable_name::TypeVariableName;
+
+    #[test]
+    #[should_panic(expected = "can't escape identifier \"Word<T>\": it contains '<'")]
+    fn invalid_name() {
+        TypeAliasSpec::builder("Word<T>", TypeName::get("", "String")).build();
+    }
+
+    #[test]
+    fn simple_alias() {
+        let alias = TypeAliasSpec::builder("Word", TypeName::get("", "String"))
+            .add_modifiers(vec![Modifier::INTERNAL])
+            .build();
+        assert_eq!("internal typealias Word = String\n", format!("{}", alias));
+    }
+
+    #[test]
+    fn generic_alias_with_kdoc() {
+        let key = TypeVariableName::new("K");
+        let map = ParameterizedTypeName::get(
+            StructName::new("", "Map"),
+            vec![TypeName::from(key.clone()), TypeName::get("", "String")],
+        );
+        let alias = TypeAliasSpec::builder("Table", TypeName::from(map))
+            .add_type_variable(key)
+            .add_kdoc("Labels keyed by %N.\n\nUsed by the menu.\n", vec!["K"])
+            .build();
+
+        assert_eq!(
+            "/**
+ * Labels keyed by K.
+ *
+ * Used by the menu.
+ */
+typealias Table<K> = Map<K, String>
+",
+            format!("{}", alias)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "modifier OPERATOR is not allowed on type alias Word")]
+    fn only_visibility_modifiers() {
+        TypeAliasSpec::builder("Word", TypeName::get("", "String"))
+            .add_modifiers(vec![Modifier::OPERATOR])
+            .build();
+    }
+}
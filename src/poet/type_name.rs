@@ -1,3 +1,9 @@
+use crate::poet::class_name::StructName;
+use crate::poet::code_writer::CodeWriter;
+use crate::poet::parameterized_type_name::ParameterizedTypeName;
+use crate::poet::type_variable_name::TypeVariableName;
+use core::fmt;
+
 /**
  * Any type in Java's type system, plus {@code void}. This class is an identifier for primitive
  * types like {@code int} and raw reference types like {@code String} and {@code List}. It also
@@ -25,5 +31,64 @@
  * {@code Set<Long>}, use the factory methods on {@link ArrayTypeName}, {@link
  * ParameterizedTypeName}, {@link TypeVariableName}, and {@link WildcardTypeName}.
  */
-#[derive(Serialize, Deserialize, Clone, Debug)]
-pub struct TypeName {}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum TypeName {
+    Struct(StructName),
+    Parameterized(ParameterizedTypeName),
+    Variable(TypeVariableName),
+}
+
+impl TypeName {
+    pub fn get(package_name: impl Into<String>, simple_name: impl Into<String>) -> TypeName {
+        TypeName::Struct(StructName::new(package_name, simple_name))
+    }
+
+    pub fn best_guess(name: &str) -> TypeName {
+        TypeName::Struct(StructName::best_guess(name))
+    }
+
+    /// Whether this is the built-in `Unit` type.
+    pub fn is_unit(&self) -> bool {
+        matches!(self, TypeName::Struct(struct_name)
+            if struct_name.package_name.is_empty() && struct_name.simple_name == "Unit")
+    }
+
+    pub fn emit(&self, writer: &mut CodeWriter) {
+        match self {
+            TypeName::Struct(struct_name) => {
+                let name = writer.lookup_name(struct_name);
+                writer.emit(name);
+            }
+            TypeName::Parameterized(parameterized) => parameterized.emit(writer),
+            TypeName::Variable(type_variable) => type_variable.emit(writer),
+        }
+    }
+}
+
+impl From<StructName> for TypeName {
+    fn from(struct_name: StructName) -> Self {
+        TypeName::Struct(struct_name)
+    }
+}
+
+impl From<ParameterizedTypeName> for TypeName {
+    fn from(parameterized: ParameterizedTypeName) -> Self {
+        TypeName::Parameterized(parameterized)
+    }
+}
+
+impl From<TypeVariableName> for TypeName {
+    fn from(type_variable: TypeVariableName) -> Self {
+        TypeName::Variable(type_variable)
+    }
+}
+
+impl fmt::Display for TypeName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TypeName::Struct(struct_name) => write!(f, "{}", struct_name),
+            TypeName::Parameterized(parameterized) => write!(f, "{}", parameterized),
+            TypeName::Variable(type_variable) => write!(f, "{}", type_variable),
+        }
+    }
+}
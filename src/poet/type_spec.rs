@@ -1,31 +1,872 @@
-use crate::poet::modifier::Modifier;
-use std::collections::HashSet;
+use crate::poet::annotation_spec::AnnotationSpec;
+use crate::poet::code_block::{Arg, CodeBlock, CodeBlockBuilder};
+use crate::poet::code_writer::CodeWriter;
+use crate::poet::method_spec::MethodSpec;
+use crate::poet::modifier::{check_modifiers, Modifier, Target};
+use crate::poet::name_allocator::NameAllocator;
+use crate::poet::parameter_spec::{emit_parameters, ParameterSpec};
+use crate::poet::property_spec::PropertySpec;
+use crate::poet::type_name::TypeName;
+use crate::poet::{check_name, escape_if_necessary, DEFAULT_INDENT};
+use core::fmt;
+use std::collections::BTreeSet;
 
 /** A generated class, interface, or enum declaration. */
 #[derive(Serialize, Deserialize, Clone, Debug)]
-pub struct TypeSpec {}
+pub struct TypeSpec {
+    pub kind: Kind,
+    pub name: String,
+    pub modifiers: Vec<Modifier>,
+    pub annotations: Vec<AnnotationSpec>,
+    pub kdoc: CodeBlock,
+    pub primary_constructor: Option<MethodSpec>,
+    pub superclass: Option<TypeName>,
+    pub superclass_constructor_parameters: Vec<CodeBlock>,
+    pub super_interfaces: Vec<TypeName>,
+    pub properties: Vec<PropertySpec>,
+    /// Init blocks, each paired with the number of properties declared before it.
+    pub initializer_blocks: Vec<(usize, CodeBlock)>,
+    pub method_specs: Vec<MethodSpec>,
+}
 
-pub struct BaseKind {
-    pub declaration_keyword: &'static String,
-    pub property_modifiers: HashSet<Modifier>,
-    pub function_modifiers: HashSet<Modifier>,
-    pub type_modifiers: HashSet<Modifier>,
+impl TypeSpec {
+    pub fn new(builder: &TypeSpecBuilder) -> Self {
+        TypeSpec {
+            kind: builder.kind.clone(),
+            name: builder.name.clone(),
+            modifiers: builder.modifiers.clone(),
+            annotations: builder.annotations.clone(),
+            kdoc: builder.kdoc.build(),
+            primary_constructor: builder.primary_constructor.clone(),
+            superclass: builder.superclass.clone(),
+            superclass_constructor_parameters: builder.superclass_constructor_parameters.clone(),
+            super_interfaces: builder.super_interfaces.clone(),
+            properties: builder.properties.clone(),
+            initializer_blocks: builder.initializer_blocks.clone(),
+            method_specs: builder.method_specs.clone(),
+        }
+    }
+
+    pub fn class_builder(name: impl Into<String>) -> TypeSpecBuilder {
+        TypeSpecBuilder::new(Kind::Class, name)
+    }
+
+    pub fn object_builder(name: impl Into<String>) -> TypeSpecBuilder {
+        TypeSpecBuilder::new(Kind::Object, name)
+    }
+
+    pub fn interface_builder(name: impl Into<String>) -> TypeSpecBuilder {
+        TypeSpecBuilder::new(Kind::Interface, name)
+    }
+
+    ///
+    /// Returns the property declared by `parameter` of the primary constructor, if any. A property
+    /// is promoted when its name and type match the parameter and its initializer is just the
+    /// parameter name, e.g. `val name: String = name`.
+    ///
+    pub fn promoted_property(&self, parameter: &ParameterSpec) -> Option<&PropertySpec> {
+        self.primary_constructor.as_ref()?;
+        self.properties.iter().find(|property| {
+            property.name == parameter.name
+                && property.type_name == parameter.type_name
+                && property.getter.is_none()
+                && property.setter.is_none()
+                && property.initializer.as_ref().is_some_and(|initializer| {
+                    initializer.to_string() == escape_if_necessary(&parameter.name)
+                })
+        })
+    }
+
+    fn is_promoted(&self, property: &PropertySpec) -> bool {
+        match &self.primary_constructor {
+            Some(constructor) => constructor.parameters.iter().any(|parameter| {
+                match self.promoted_property(parameter) {
+                    Some(promoted) => promoted.name == property.name,
+                    None => false,
+                }
+            }),
+            None => false,
+        }
+    }
+
+    fn has_no_body(&self) -> bool {
+        let primary_constructor_body = match &self.primary_constructor {
+            Some(constructor) => !constructor.code.is_empty(),
+            None => false,
+        };
+
+        !primary_constructor_body
+            && self.initializer_blocks.is_empty()
+            && self.method_specs.is_empty()
+            && self
+                .properties
+                .iter()
+                .all(|property| self.is_promoted(property))
+    }
+
+    ///
+    /// Returns the doc comment of this type followed by tags for the documented primary
+    /// constructor parameters: `@property` for promoted properties, `@param` otherwise.
+    ///
+    pub fn kdoc_with_tags(&self) -> CodeBlock {
+        let mut builder = CodeBlockBuilder::new();
+        builder.add_code(&self.kdoc.ensure_ends_with_newline());
+
+        let mut tags = CodeBlockBuilder::new();
+        let parameters = self
+            .primary_constructor
+            .iter()
+            .flat_map(|constructor| constructor.parameters.iter());
+        for parameter in parameters {
+            let property = self
+                .promoted_property(parameter)
+                .filter(|property| !property.kdoc.is_empty());
+            let (tag, kdoc) = match property {
+                Some(property) => ("@property", &property.kdoc),
+                None => ("@param", &parameter.kdoc),
+            };
+            if !kdoc.is_empty() {
+                tags.add("%L %L ", vec![String::from(tag), parameter.name.clone()]);
+                tags.add_code(&kdoc.ensure_ends_with_newline());
+            }
+        }
+
+        if !self.kdoc.is_empty() && !tags.is_empty() {
+            builder.add("\n", Vec::<Arg>::new());
+        }
+        builder.add_code(&tags.build());
+        builder.build()
+    }
+
+    pub fn emit(&self, writer: &mut CodeWriter) {
+        writer.emit_kdoc(&self.kdoc_with_tags());
+        writer.emit_annotations(&self.annotations, false);
+        writer.emit_modifiers(&self.modifiers);
+        let keyword = String::from(self.kind.value().declaration_keyword);
+        writer.emit_code("%L·%N", vec![keyword, self.name.clone()]);
+
+        if let Some(constructor) = &self.primary_constructor {
+            if !constructor.modifiers.is_empty() {
+                writer.emit(String::from(" "));
+                writer.emit_modifiers(&constructor.modifiers);
+                writer.emit(String::from("constructor"));
+            }
+
+            emit_parameters(
+                writer,
+                &constructor.parameters,
+                |writer, parameter| match self.promoted_property(parameter) {
+                    Some(property) => {
+                        parameter.emit_annotations_and_modifiers(writer);
+                        property.emit(writer, true);
+                        parameter.emit_default_value(writer);
+                    }
+                    None => parameter.emit(writer),
+                },
+                |writer| {
+                    self.emit_super_types(writer);
+                    self.emit_body_start(writer);
+                },
+            );
+        }
+
+        self.emit_super_types(writer);
+        self.emit_body_start(writer);
+        if self.has_no_body() {
+            return;
+        }
+
+        writer.indent(1);
+        let mut first_member = true;
+
+        if let Some(constructor) = &self.primary_constructor {
+            if !constructor.code.is_empty() {
+                TypeSpec::emit_init_block(writer, &constructor.code);
+                first_member = false;
+            }
+        }
+
+        for (index, property) in self.properties.iter().enumerate() {
+            first_member = self.emit_init_blocks_at(writer, index, first_member);
+            if self.is_promoted(property) {
+                continue;
+            }
+            if !first_member {
+                writer.emit_member_separator();
+            }
+            property.emit(writer, false);
+            first_member = false;
+        }
+        first_member = self.emit_init_blocks_at(writer, self.properties.len(), first_member);
+
+        // Secondary constructors come before functions.
+        let constructors = self
+            .method_specs
+            .iter()
+            .filter(|method| method.is_constructor());
+        let functions = self
+            .method_specs
+            .iter()
+            .filter(|method| !method.is_constructor());
+        for method in constructors.chain(functions) {
+            if !first_member {
+                writer.emit_member_separator();
+            }
+            method.emit(writer);
+            first_member = false;
+        }
+
+        writer.unindent(1);
+        writer.emit(String::from("}\n"));
+    }
+
+    /// Emits the opening brace of the body, or the line break ending a type without one.
+    fn emit_body_start(&self, writer: &mut CodeWriter) {
+        if self.has_no_body() {
+            writer.emit(String::from("\n"));
+        } else {
+            writer.emit_open_brace();
+        }
+    }
+
+    fn emit_super_types(&self, writer: &mut CodeWriter) {
+        if self.superclass.is_none() && self.super_interfaces.is_empty() {
+            return;
+        }
+
+        writer.emit(String::from(" :"));
+        let mut first = true;
+        if let Some(superclass) = &self.superclass {
+            writer.emit(String::from(" "));
+            superclass.emit(writer);
+            // Without a primary constructor, secondary constructors call `super(...)` instead.
+            let has_secondary_constructors = self.method_specs.iter().any(|m| m.is_constructor());
+            if self.primary_constructor.is_some() || !has_secondary_constructors {
+                let parameters = &self.superclass_constructor_parameters;
+                writer.emit(String::from("("));
+                writer.emit_group(
+                    parameters.len(),
+                    |writer, index| writer.emit_block(&parameters[index]),
+                    |writer| {
+                        writer.emit(String::from(")"));
+                        self.emit_super_interfaces(writer, false);
+                        self.emit_body_start(writer);
+                    },
+                );
+                writer.emit(String::from(")"));
+            }
+            first = false;
+        }
+
+        self.emit_super_interfaces(writer, first);
+    }
+
+    fn emit_super_interfaces(&self, writer: &mut CodeWriter, first: bool) {
+        let mut first = first;
+        for super_interface in self.super_interfaces.iter() {
+            if !first {
+                writer.emit(String::from(","));
+            }
+            writer.emit(String::from(" "));
+            super_interface.emit(writer);
+            first = false;
+        }
+    }
+
+    fn emit_init_blocks_at(
+        &self,
+        writer: &mut CodeWriter,
+        index: usize,
+        first_member: bool,
+    ) -> bool {
+        let mut first_member = first_member;
+        for (property_index, code) in self.initializer_blocks.iter() {
+            if *property_index != index {
+                continue;
+            }
+            if !first_member {
+                writer.emit_member_separator();
+            }
+            TypeSpec::emit_init_block(writer, code);
+            first_member = false;
+        }
+        first_member
+    }
+
+    fn emit_init_block(writer: &mut CodeWriter, code: &CodeBlock) {
+        writer.emit(String::from("init"));
+        writer.emit_open_brace();
+        writer.indent(1);
+        writer.emit_block(code);
+        writer.unindent(1);
+        writer.emit(String::from("}\n"));
+    }
 }
 
-pub struct ClassKind {
-    pub kind: BaseKind,
+impl fmt::Display for TypeSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut out = String::new();
+        let mut writer = CodeWriter::new(&mut out, DEFAULT_INDENT);
+        self.emit(&mut writer);
+        writer.close();
+        write!(f, "{}", out)
+    }
 }
 
-pub struct ObjectKind {
-    pub kind: BaseKind,
+#[derive(Serialize, Clone, Debug)]
+pub struct TypeSpecBuilder {
+    pub kind: Kind,
+    pub name: String,
+    pub modifiers: Vec<Modifier>,
+    pub annotations: Vec<AnnotationSpec>,
+    pub kdoc: CodeBlockBuilder,
+    pub primary_constructor: Option<MethodSpec>,
+    pub superclass: Option<TypeName>,
+    pub superclass_constructor_parameters: Vec<CodeBlock>,
+    pub super_interfaces: Vec<TypeName>,
+    pub properties: Vec<PropertySpec>,
+    pub initializer_blocks: Vec<(usize, CodeBlock)>,
+    pub method_specs: Vec<MethodSpec>,
+    pub strip_implicit_modifiers: bool,
 }
 
-pub struct InterfaceKind {
-    pub kind: BaseKind,
+impl TypeSpecBuilder {
+    pub fn new(kind: Kind, name: impl Into<String>) -> Self {
+        TypeSpecBuilder {
+            kind,
+            name: name.into(),
+            modifiers: vec![],
+            annotations: vec![],
+            kdoc: CodeBlockBuilder::new(),
+            primary_constructor: None,
+            superclass: None,
+            superclass_constructor_parameters: vec![],
+            super_interfaces: vec![],
+            properties: vec![],
+            initializer_blocks: vec![],
+            method_specs: vec![],
+            strip_implicit_modifiers: false,
+        }
+    }
+
+    ///
+    /// Returns a scope holding the names visible in this type's members: its properties and
+    /// primary constructor parameters. See `MethodSpecBuilder::enclosing_scope`.
+    ///
+    pub fn name_allocator(&self) -> NameAllocator {
+        let mut name_allocator = NameAllocator::new();
+        for property in self.properties.iter() {
+            name_allocator.reserve(&property.name);
+        }
+        if let Some(constructor) = &self.primary_constructor {
+            for parameter in constructor.parameters.iter() {
+                name_allocator.reserve(&parameter.name);
+            }
+        }
+        name_allocator
+    }
+
+    ///
+    /// Drops modifiers that restate a default, like an explicit `public`, from this type and its
+    /// members when building.
+    ///
+    pub fn strip_implicit_modifiers(&mut self, strip: bool) -> &mut TypeSpecBuilder {
+        self.strip_implicit_modifiers = strip;
+        self
+    }
+
+    pub fn add_kdoc<T: Into<Arg>>(&mut self, format: &str, args: Vec<T>) -> &mut TypeSpecBuilder {
+        self.kdoc.add(format, args);
+        self
+    }
+
+    pub fn add_annotation(&mut self, annotation: AnnotationSpec) -> &mut TypeSpecBuilder {
+        self.annotations.push(annotation);
+        self
+    }
+
+    pub fn add_modifiers(&mut self, modifiers: Vec<Modifier>) -> &mut TypeSpecBuilder {
+        self.modifiers.extend(modifiers);
+        self
+    }
+
+    pub fn primary_constructor(&mut self, constructor: MethodSpec) -> &mut TypeSpecBuilder {
+        if self.kind != Kind::Class {
+            panic!("{:?} can't have constructors", self.kind);
+        }
+        if !constructor.is_constructor() {
+            panic!("expected a constructor but was {}", constructor.name);
+        }
+        if constructor.delegate_constructor.is_some() {
+            panic!("primary constructor can't delegate to other constructors");
+        }
+        self.primary_constructor = Some(constructor);
+        self
+    }
+
+    pub fn superclass(&mut self, superclass: TypeName) -> &mut TypeSpecBuilder {
+        if self.kind != Kind::Class {
+            panic!("only classes can have super classes, not {:?}", self.kind);
+        }
+        self.superclass = Some(superclass);
+        self
+    }
+
+    pub fn add_superclass_constructor_parameter<T: Into<Arg>>(
+        &mut self,
+        format: &str,
+        args: Vec<T>,
+    ) -> &mut TypeSpecBuilder {
+        self.superclass_constructor_parameters
+            .push(CodeBlock::of(format, args));
+        self
+    }
+
+    pub fn add_super_interface(&mut self, super_interface: TypeName) -> &mut TypeSpecBuilder {
+        self.super_interfaces.push(super_interface);
+        self
+    }
+
+    pub fn add_property(&mut self, property: PropertySpec) -> &mut TypeSpecBuilder {
+        self.properties.push(property);
+        self
+    }
+
+    ///
+    /// Adds an `init` block. Init blocks and property initializers run in declaration order, so
+    /// the block is emitted after the properties added so far.
+    ///
+    pub fn add_init_block(&mut self, block: CodeBlock) -> &mut TypeSpecBuilder {
+        if self.kind == Kind::Interface {
+            panic!("{:?} can't have initializer blocks", self.kind);
+        }
+        self.initializer_blocks.push((self.properties.len(), block));
+        self
+    }
+
+    pub fn add_method(&mut self, method: MethodSpec) -> &mut TypeSpecBuilder {
+        if method.is_constructor() && self.kind != Kind::Class {
+            panic!("{:?} can't have constructors", self.kind);
+        }
+        self.method_specs.push(method);
+        self
+    }
+
+    pub fn build(&self) -> TypeSpec {
+        let target = match self.kind {
+            Kind::Interface => Target::INTERFACE,
+            _ => Target::CLASS,
+        };
+        check_name(&self.name);
+        let kind = self.kind.value();
+        let declaration = format!("{} {}", kind.declaration_keyword, self.name);
+        check_modifiers(&self.modifiers, target, &declaration);
+
+        let member_modifiers = self
+            .properties
+            .iter()
+            .flat_map(|property| property.modifiers.iter())
+            .chain(
+                self.method_specs
+                    .iter()
+                    .flat_map(|method| method.modifiers.iter()),
+            );
+        for modifier in member_modifiers {
+            if kind.forbidden_member_modifiers.contains(modifier) {
+                panic!("modifier {:?} is not allowed in {}", modifier, declaration);
+            }
+        }
+
+        if self.primary_constructor.is_some() {
+            for method in self.method_specs.iter().filter(|m| m.is_constructor()) {
+                if method.delegate_constructor.as_deref() != Some("this") {
+                    panic!(
+                        "secondary constructors of {} must delegate to this()",
+                        self.name
+                    );
+                }
+            }
+        }
+
+        if !self.superclass_constructor_parameters.is_empty() {
+            if self.superclass.is_none() {
+                panic!("superclass constructor parameters require a superclass");
+            }
+            let has_secondary_constructors = self.method_specs.iter().any(|m| m.is_constructor());
+            if self.primary_constructor.is_none() && has_secondary_constructors {
+                panic!("superclass constructor parameters require a primary constructor");
+            }
+        }
+
+        let mut type_spec = TypeSpec::new(self);
+        if self.strip_implicit_modifiers {
+            type_spec
+                .modifiers
+                .retain(|modifier| *modifier != Modifier::PUBLIC);
+            for property in type_spec.properties.iter_mut() {
+                property
+                    .modifiers
+                    .retain(|modifier| !kind.property_modifiers.contains(modifier));
+            }
+            for method in type_spec.method_specs.iter_mut() {
+                method
+                    .modifiers
+                    .retain(|modifier| !kind.function_modifiers.contains(modifier));
+            }
+        }
+        type_spec
+    }
+}
+
+pub struct BaseKind {
+    pub declaration_keyword: &'static str,
+    pub property_modifiers: BTreeSet<Modifier>,
+    pub function_modifiers: BTreeSet<Modifier>,
+    pub type_modifiers: BTreeSet<Modifier>,
+    /// Modifiers that members of this kind of type can't have.
+    pub forbidden_member_modifiers: BTreeSet<Modifier>,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub enum Kind {
-    Class(ClassKind),
-    Object(ObjectKind),
-    Interface(InterfaceKind),
+    Class,
+    Object,
+    Interface,
+}
+
+impl Kind {
+    pub fn value(&self) -> BaseKind {
+        match *self {
+            Kind::Class => BaseKind {
+                declaration_keyword: "class",
+                property_modifiers: [Modifier::PUBLIC].iter().cloned().collect(),
+                function_modifiers: [Modifier::PUBLIC].iter().cloned().collect(),
+                type_modifiers: BTreeSet::new(),
+                forbidden_member_modifiers: BTreeSet::new(),
+            },
+            Kind::Object => BaseKind {
+                declaration_keyword: "object",
+                property_modifiers: [Modifier::PUBLIC].iter().cloned().collect(),
+                function_modifiers: [Modifier::PUBLIC].iter().cloned().collect(),
+                type_modifiers: BTreeSet::new(),
+                forbidden_member_modifiers: [
+                    Modifier::PROTECTED,
+                    Modifier::ABSTRACT,
+                    Modifier::OPEN,
+                ]
+                .iter()
+                .cloned()
+                .collect(),
+            },
+            Kind::Interface => BaseKind {
+                declaration_keyword: "interface",
+                property_modifiers: [Modifier::PUBLIC].iter().cloned().collect(),
+                function_modifiers: [Modifier::PUBLIC].iter().cloned().collect(),
+                type_modifiers: BTreeSet::new(),
+                forbidden_member_modifiers: [
+                    Modifier::PROTECTED,
+                    Modifier::FINAL,
+                    Modifier::LATEINIT,
+                ]
+                .iter()
+                .cloned()
+                .collect(),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::poet::code_block::CodeBlock;
+    use crate::poet::method_spec::MethodSpec;
+    use crate::poet::modifier::Modifier;
+    use crate::poet::parameter_spec::ParameterSpec;
+    use crate::poet::property_spec::PropertySpec;
+    use crate::poet::type_name::TypeName;
+    use crate::poet::type_spec::TypeSpec;
+
+    fn int() -> TypeName {
+        TypeName::get("", "Int")
+    }
+
+    #[test]
+    #[should_panic(expected = "can't escape identifier \"Taco<T>\": it contains '<'")]
+    fn invalid_name() {
+        TypeSpec::class_builder("Taco<T>").build();
+    }
+
+    #[test]
+    fn empty_class() {
+        let taco = TypeSpec::class_builder("Taco").build();
+        assert_eq!("class Taco\n", format!("{}", taco));
+    }
+
+    #[test]
+    fn primary_constructor_promotes_parameters_to_properties() {
+        let constructor = MethodSpec::constructor_builder()
+            .add_parameter(ParameterSpec::builder("id", int()).build())
+            .add_parameter(ParameterSpec::builder("count", int()).build())
+            .build();
+        let id = PropertySpec::builder("id", int())
+            .initializer("%N", vec![String::from("id")])
+            .build();
+        let taco = TypeSpec::class_builder("Taco")
+            .primary_constructor(constructor)
+            .add_property(id)
+            .build();
+
+        assert_eq!("class Taco(val id: Int, count: Int)\n", format!("{}", taco));
+    }
+
+    #[test]
+    fn promoted_parameter_keeps_default_value() {
+        let constructor = MethodSpec::constructor_builder()
+            .add_parameter(
+                ParameterSpec::builder("id", int())
+                    .default_value("%L", vec!["0"])
+                    .build(),
+            )
+            .build();
+        let id = PropertySpec::builder("id", int())
+            .initializer("%N", vec!["id"])
+            .build();
+        let taco = TypeSpec::class_builder("Taco")
+            .primary_constructor(constructor)
+            .add_property(id)
+            .build();
+
+        assert_eq!("class Taco(val id: Int = 0)\n", format!("{}", taco));
+    }
+
+    #[test]
+    fn keyword_names_are_escaped() {
+        let constructor = MethodSpec::constructor_builder()
+            .add_parameter(ParameterSpec::builder("type", int()).build())
+            .add_parameter(ParameterSpec::builder("in", int()).build())
+            .build();
+        let type_property = PropertySpec::builder("type", int())
+            .initializer("%N", vec!["type"])
+            .build();
+        let taco = TypeSpec::class_builder("Taco")
+            .primary_constructor(constructor)
+            .add_property(type_property)
+            .add_property(
+                PropertySpec::builder("größe", TypeName::get("com.in", "Size"))
+                    .initializer("%N", vec!["in"])
+                    .build(),
+            )
+            .build();
+
+        assert_eq!(
+            "class Taco(val `type`: Int, `in`: Int) {
+    val `größe`: com.`in`.Size = `in`
+}
+",
+            format!("{}", taco)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "can't escape identifier \"Taco<T>\": it contains '<'")]
+    fn unescapable_type_name() {
+        TypeSpec::class_builder("Taco<T>").build().to_string();
+    }
+
+    #[test]
+    fn primary_constructor_with_modifiers() {
+        let constructor = MethodSpec::constructor_builder()
+            .add_modifiers(vec![Modifier::PRIVATE])
+            .add_parameter(ParameterSpec::builder("id", int()).build())
+            .build();
+        let taco = TypeSpec::class_builder("Taco")
+            .primary_constructor(constructor)
+            .build();
+
+        assert_eq!(
+            "class Taco private constructor(id: Int)\n",
+            format!("{}", taco)
+        );
+    }
+
+    #[test]
+    fn init_blocks_in_declaration_order() {
+        let taco = TypeSpec::class_builder("Taco")
+            .add_property(
+                PropertySpec::builder("a", int())
+                    .initializer("%L", vec![String::from("1")])
+                    .build(),
+            )
+            .add_init_block(CodeBlock::of("println(%N)\n", vec!["a"]))
+            .add_property(
+                PropertySpec::builder("b", int())
+                    .initializer("%L", vec![String::from("2")])
+                    .build(),
+            )
+            .add_init_block(CodeBlock::of("println(%N)\n", vec!["b"]))
+            .build();
+
+        assert_eq!(
+            "class Taco {
+    val a: Int = 1
+
+    init {
+        println(a)
+    }
+
+    val b: Int = 2
+
+    init {
+        println(b)
+    }
+}
+",
+            format!("{}", taco)
+        );
+    }
+
+    #[test]
+    fn secondary_constructor_delegation() {
+        let primary = MethodSpec::constructor_builder()
+            .add_parameter(ParameterSpec::builder("a", int()).build())
+            .add_parameter(ParameterSpec::builder("b", int()).build())
+            .build();
+        let secondary = MethodSpec::constructor_builder()
+            .add_parameter(ParameterSpec::builder("a", int()).build())
+            .call_this_constructor(vec![
+                CodeBlock::of("%N", vec![String::from("a")]),
+                CodeBlock::of("%L", vec![String::from("0")]),
+            ])
+            .build();
+        let taco = TypeSpec::class_builder("Taco")
+            .primary_constructor(primary)
+            .superclass(TypeName::get("com.phodal", "Food"))
+            .add_superclass_constructor_parameter("%N", vec![String::from("a")])
+            .add_method(secondary)
+            .build();
+
+        assert_eq!(
+            "class Taco(a: Int, b: Int) : com.phodal.Food(a) {
+    constructor(a: Int) : this(a, 0)
+}
+",
+            format!("{}", taco)
+        );
+    }
+
+    #[test]
+    fn secondary_constructors_call_super_without_primary() {
+        let secondary = MethodSpec::constructor_builder()
+            .add_parameter(ParameterSpec::builder("a", int()).build())
+            .call_super_constructor(vec![CodeBlock::of("%N", vec![String::from("a")])])
+            .build();
+        let taco = TypeSpec::class_builder("Taco")
+            .superclass(TypeName::get("", "Food"))
+            .add_method(secondary)
+            .build();
+
+        assert_eq!(
+            "class Taco : Food {\n    constructor(a: Int) : super(a)\n}\n",
+            format!("{}", taco)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "secondary constructors of Taco must delegate to this()")]
+    fn secondary_constructor_must_delegate_to_primary() {
+        TypeSpec::class_builder("Taco")
+            .primary_constructor(MethodSpec::constructor_builder().build())
+            .add_method(MethodSpec::constructor_builder().build())
+            .build();
+    }
+
+    #[test]
+    #[should_panic(expected = "Object can't have constructors")]
+    fn object_cannot_have_constructors() {
+        TypeSpec::object_builder("Taco")
+            .primary_constructor(MethodSpec::constructor_builder().build());
+    }
+
+    #[test]
+    #[should_panic(expected = "modifier PROTECTED is not allowed in object Taco")]
+    fn protected_member_in_object() {
+        TypeSpec::object_builder("Taco")
+            .add_property(
+                PropertySpec::builder("filling", TypeName::get("", "String"))
+                    .add_modifiers(vec![Modifier::PROTECTED])
+                    .initializer("%S", vec!["beef"])
+                    .build(),
+            )
+            .build();
+    }
+
+    #[test]
+    fn strips_implicit_public() {
+        let taco = TypeSpec::class_builder("Taco")
+            .add_modifiers(vec![Modifier::PUBLIC, Modifier::OPEN])
+            .add_property(
+                PropertySpec::builder("filling", TypeName::get("", "String"))
+                    .add_modifiers(vec![Modifier::PUBLIC])
+                    .initializer("%S", vec!["beef"])
+                    .build(),
+            )
+            .add_method(
+                MethodSpec::builder("eat")
+                    .add_modifiers(vec![Modifier::PUBLIC, Modifier::OPEN])
+                    .build(),
+            )
+            .strip_implicit_modifiers(true)
+            .build();
+
+        assert_eq!(
+            "open class Taco {
+    val filling: String = \"beef\"
+
+    open fun eat() {
+    }
+}
+",
+            format!("{}", taco)
+        );
+    }
+
+    #[test]
+    fn kdoc_documents_constructor_parameters() {
+        let constructor = MethodSpec::constructor_builder()
+            .add_parameter(
+                ParameterSpec::builder("id", int())
+                    .add_kdoc("the id", Vec::<String>::new())
+                    .build(),
+            )
+            .add_parameter(
+                ParameterSpec::builder("size", int())
+                    .add_kdoc("ignored in favour of the property", Vec::<String>::new())
+                    .build(),
+            )
+            .build();
+        let size = PropertySpec::builder("size", int())
+            .initializer("%N", vec!["size"])
+            .add_kdoc("the size in inches", Vec::<String>::new())
+            .build();
+        let taco = TypeSpec::class_builder("Taco")
+            .add_kdoc(
+                "A [%T] with fillings.\n",
+                vec![TypeName::get("com.phodal", "Taco")],
+            )
+            .primary_constructor(constructor)
+            .add_property(size)
+            .build();
+
+        assert_eq!(
+            "/**
+ * A [com.phodal.Taco] with fillings.
+ *
+ * @param id the id
+ * @property size the size in inches
+ */
+class Taco(id: Int, val size: Int)
+",
+            format!("{}", taco)
+        );
+    }
 }
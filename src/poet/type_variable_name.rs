@@ -1,2 +1,156 @@
-#[derive(Serialize, Deserialize, Clone, Debug)]
-pub struct TypeVariableName {}
+use crate::poet::code_writer::CodeWriter;
+use crate::poet::modifier::{Modifier, Target};
+use crate::poet::type_name::TypeName;
+use core::fmt;
+
+///
+/// A type variable such as `T` in `List<T>`. Bounds are only emitted where the variable is
+/// declared, e.g. `<T : Comparable<T>>`; references to the variable use its bare name.
+///
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TypeVariableName {
+    pub name: String,
+    pub bounds: Vec<TypeName>,
+    pub variance: Option<Modifier>,
+    pub is_reified: bool,
+}
+
+impl TypeVariableName {
+    pub fn new(name: impl Into<String>) -> Self {
+        TypeVariableName {
+            name: name.into(),
+            bounds: vec![],
+            variance: None,
+            is_reified: false,
+        }
+    }
+
+    ///
+    /// Returns a variable declared with `bounds`. Declarations have no `where` clause to put
+    /// further bounds in, so at most one bound is allowed.
+    ///
+    pub fn with_bounds(name: impl Into<String>, bounds: Vec<TypeName>) -> Self {
+        let type_variable = TypeVariableName {
+            bounds,
+            ..TypeVariableName::new(name)
+        };
+        type_variable.check_bounds();
+        type_variable
+    }
+
+    fn check_bounds(&self) {
+        if self.bounds.len() > 1 {
+            panic!(
+                "type variable {} has {} bounds, only one is allowed",
+                self.name,
+                self.bounds.len()
+            );
+        }
+    }
+
+    /// Returns a copy of this variable declared with the `in` or `out` variance.
+    pub fn with_variance(&self, variance: Modifier) -> Self {
+        if !variance.is_allowed_on(&Target::VARIANCE_ANNOTATION) {
+            panic!("{:?} is not a variance modifier", variance);
+        }
+        TypeVariableName {
+            variance: Some(variance),
+            ..self.clone()
+        }
+    }
+
+    /// Returns a copy of this variable declared `reified`, for use in inline functions.
+    pub fn reified(&self) -> Self {
+        TypeVariableName {
+            is_reified: true,
+            ..self.clone()
+        }
+    }
+
+    pub fn emit(&self, writer: &mut CodeWriter) {
+        writer.emit(self.name.clone());
+    }
+
+    ///
+    /// Emits this variable as it appears in a declaration, followed by its bounds.
+    ///
+    pub fn emit_declaration(&self, writer: &mut CodeWriter) {
+        self.check_bounds();
+        if self.is_reified {
+            writer.emit_modifiers(&[Modifier::REIFIED]);
+        }
+        if let Some(variance) = &self.variance {
+            writer.emit_modifiers(std::slice::from_ref(variance));
+        }
+        self.emit(writer);
+        if let Some(bound) = self.bounds.first() {
+            writer.emit(String::from(" : "));
+            bound.emit(writer);
+        }
+    }
+}
+
+impl fmt::Display for TypeVariableName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+///
+/// Emits a type variable declaration list like `<K, V : Comparable<V>>`. Nothing is emitted when
+/// `type_variables` is empty.
+///
+pub fn emit_type_variables(writer: &mut CodeWriter, type_variables: &[TypeVariableName]) {
+    if type_variables.is_empty() {
+        return;
+    }
+
+    writer.emit(String::from("<"));
+    for (index, type_variable) in type_variables.iter().enumerate() {
+        if index > 0 {
+            writer.emit(String::from(", "));
+        }
+        type_variable.emit_declaration(writer);
+    }
+    writer.emit(String::from(">"));
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::poet::code_writer::CodeWriter;
+    use crate::poet::modifier::Modifier;
+    use crate::poet::type_name::TypeName;
+    use crate::poet::type_variable_name::{emit_type_variables, TypeVariableName};
+    use crate::poet::DEFAULT_INDENT;
+
+    #[test]
+    fn declaration_with_variance_and_bounds() {
+        let mut out = String::new();
+        let mut writer = CodeWriter::new(&mut out, DEFAULT_INDENT);
+        emit_type_variables(
+            &mut writer,
+            &[
+                TypeVariableName::new("K").reified(),
+                TypeVariableName::with_bounds("V", vec![TypeName::get("", "Taco")])
+                    .with_variance(Modifier::OUT),
+            ],
+        );
+        writer.close();
+        assert_eq!("<reified K, out V : Taco>", out);
+    }
+
+    #[test]
+    #[should_panic(expected = "type variable V has 2 bounds, only one is allowed")]
+    fn at_most_one_bound() {
+        TypeVariableName::with_bounds(
+            "V",
+            vec![TypeName::get("", "Taco"), TypeName::get("", "Burrito")],
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "OPEN is not a variance modifier")]
+    fn variance_must_be_in_or_out() {
+        TypeVariableName::new("T").with_variance(Modifier::OPEN);
+    }
+}